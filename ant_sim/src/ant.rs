@@ -1,178 +1,74 @@
-pub enum AntRole {
-    Worker,
-    Soldier,
-    Scout,
-    Queen,
-}
-
-impl AntRole {
-    pub const COUNT: u8 = 4;
-}
-
-impl From<AntRole> for String {
-    fn from(role: AntRole) -> Self {
-        match role {
-            AntRole::Worker => "Worker".to_string(),
-            AntRole::Soldier => "Soldier".to_string(),
-            AntRole::Scout => "Scout".to_string(),
-            AntRole::Queen => "Queen".to_string(),
-        }
-    }
-}
-
-impl From<u8> for AntRole {
-    fn from(value: u8) -> Self {
-        match value & 0b11 {
-            0 => AntRole::Worker,
-            1 => AntRole::Soldier,
-            2 => AntRole::Scout,
-            3 => AntRole::Queen,
-            _ => unreachable!()
-        }
-    }
-}
-
-impl From<AntRole> for u8 {
-    fn from(role: AntRole) -> Self {
-        match role {
-            AntRole::Worker => 0,
-            AntRole::Soldier => 1,
-            AntRole::Scout => 2,
-            AntRole::Queen => 3,
-        }
+use crate::constants;
+use crate::packed::packed_enum;
+
+
+packed_enum! {
+    /// What an ant does for the colony, and so which brain drives it
+    pub enum AntRole {
+        /// Forages and digs
+        Worker,
+        /// Hunts predators; stronger and tougher
+        Soldier,
+        /// Fast and fragile
+        Scout,
+        /// Tough but doesn't fight
+        Queen,
     }
 }
 
 // *****************************************************
 
-pub enum Carrying {
-    None,
-    Food,
-    a,
-    b,
-}
-
-impl Carrying {
-    pub const COUNT: u8 = 4;
-}
-
-impl From<Carrying> for String {
-    fn from(carrying: Carrying) -> Self {
-        match carrying {
-            Carrying::None => "None".to_string(),
-            Carrying::Food => "Food".to_string(),
-            Carrying::a => "a".to_string(),
-            Carrying::b => "b".to_string(),
-        }
-    }
-}
-
-impl From<u8> for Carrying {
-    fn from(value: u8) -> Self {
-        match value & 0b11 {
-            0 => Carrying::None,
-            1 => Carrying::Food,
-            2 => Carrying::a,
-            3 => Carrying::b,
-            _ => unreachable!()
-        }
-    }
-}
-
-impl From<Carrying> for u8 {
-    fn from(carrying: Carrying) -> Self {
-        match carrying {
-            Carrying::None => 0,
-            Carrying::Food => 1,
-            Carrying::a => 2,
-            Carrying::b => 3,
-        }
+packed_enum! {
+    /// What an ant holds in its mandibles
+    pub enum Carrying {
+        /// Nothing
+        None,
+        /// Food on its way to the nest
+        Food,
+        /// Soil dug out of a tunnel
+        Soil,
+        /// Unused
+        #[allow(non_camel_case_types)]
+        b,
     }
 }
 
 // *****************************************************
 
-pub enum UniqueFlag {
-    None,
-    Flag1,
-    Flag2,
-    Flag3,
-    Flag4,
-    Flag5,
-    Flag6,
-    Flag7,
-}
-
-impl UniqueFlag {
-    pub const COUNT: u8 = 8;
-}
-
-impl From<UniqueFlag> for String {
-    fn from(flag: UniqueFlag) -> Self {
-        match flag {
-            UniqueFlag::None => "None".to_string(),
-            UniqueFlag::Flag1 => "Flag1".to_string(),
-            UniqueFlag::Flag2 => "Flag2".to_string(),
-            UniqueFlag::Flag3 => "Flag3".to_string(),
-            UniqueFlag::Flag4 => "Flag4".to_string(),
-            UniqueFlag::Flag5 => "Flag5".to_string(),
-            UniqueFlag::Flag6 => "Flag6".to_string(),
-            UniqueFlag::Flag7 => "Flag7".to_string(),
-        }
-    }
-}
-
-impl From<u8> for UniqueFlag {
-    fn from(value: u8) -> Self {
-        match value & 0b111 {
-            0 => UniqueFlag::None,
-            1 => UniqueFlag::Flag1,
-            2 => UniqueFlag::Flag2,
-            3 => UniqueFlag::Flag3,
-            4 => UniqueFlag::Flag4,
-            5 => UniqueFlag::Flag5,
-            6 => UniqueFlag::Flag6,
-            7 => UniqueFlag::Flag7,
-            _ => unreachable!()
-        }
-    }
-}
-
-impl From<UniqueFlag> for u8 {
-    fn from(flag: UniqueFlag) -> Self {
-        match flag {
-            UniqueFlag::None => 0,
-            UniqueFlag::Flag1 => 1,
-            UniqueFlag::Flag2 => 2,
-            UniqueFlag::Flag3 => 3,
-            UniqueFlag::Flag4 => 4,
-            UniqueFlag::Flag5 => 5,
-            UniqueFlag::Flag6 => 6,
-            UniqueFlag::Flag7 => 7,
-        }
+packed_enum! {
+    /// Three spare bits of an ant; not used by the simulation
+    #[allow(missing_docs)]
+    pub enum UniqueFlag {
+        None,
+        Flag1,
+        Flag2,
+        Flag3,
+        Flag4,
+        Flag5,
+        Flag6,
+        Flag7,
     }
 }
 
 // *****************************************************
 
-pub enum Direction {
-    Up,
-    UpRight,
-    Right,
-    DownRight,
-    Down,
-    DownLeft,
-    Left,
-    UpLeft,
-}
-
-impl Direction {
-    pub const COUNT: u8 = 8;
+packed_enum! {
+    /// One of the eight headings, clockwise from straight up
+    #[allow(missing_docs)]
+    pub enum Direction {
+        Up,
+        UpRight,
+        Right,
+        DownRight,
+        Down,
+        DownLeft,
+        Left,
+        UpLeft,
+    }
 }
 
 impl Direction {
-    const DIRECTION_COUNT: u8 = 8;
-
+    /// The (dx, dy) step in this direction; y grows downwards
     pub fn delta(self) -> (isize, isize) {
         match self {
             Direction::Up => (0, -1),
@@ -186,55 +82,41 @@ impl Direction {
         }
     }
 
+    /// An eighth of a turn anticlockwise
     pub fn turn_left(self) -> Self {
-        Direction::from((self as u8 + 7) % Self::DIRECTION_COUNT)
+        Direction::from_bits(self as u8 + 7)
     }
 
+    /// An eighth of a turn clockwise
     pub fn turn_right(self) -> Self {
-        Direction::from((self as u8 + 1) % Self::DIRECTION_COUNT)
+        Direction::from_bits(self as u8 + 1)
     }
-}
 
-impl From<Direction> for String {
-    fn from(direction: Direction) -> Self {
-        match direction {
-            Direction::Up => "Up".to_string(),
-            Direction::UpRight => "UpRight".to_string(),
-            Direction::Right => "Right".to_string(),
-            Direction::DownRight => "DownRight".to_string(),
-            Direction::Down => "Down".to_string(),
-            Direction::DownLeft => "DownLeft".to_string(),
-            Direction::Left => "Left".to_string(),
-            Direction::UpLeft => "UpLeft".to_string(),
-        }
+    /// The opposite direction
+    pub fn reverse(self) -> Self {
+        Direction::from_bits(self as u8 + 4)
     }
-}
 
-impl From<u8> for Direction {
-    fn from(value: u8) -> Self {
-        match value & 0b111 {
-            0 => Direction::Up,
-            1 => Direction::UpRight,
-            2 => Direction::Right,
-            3 => Direction::DownRight,
-            4 => Direction::Down,
-            5 => Direction::DownLeft,
-            6 => Direction::Left,
-            7 => Direction::UpLeft,
-            _ => unreachable!()
+    /// The direction (dx, dy) points in, going by sign only; None for (0, 0)
+    pub fn from_delta(dx: isize, dy: isize) -> Option<Self> {
+        match (dx.signum(), dy.signum()) {
+            (0, -1) => Some(Direction::Up),
+            (1, -1) => Some(Direction::UpRight),
+            (1, 0) => Some(Direction::Right),
+            (1, 1) => Some(Direction::DownRight),
+            (0, 1) => Some(Direction::Down),
+            (-1, 1) => Some(Direction::DownLeft),
+            (-1, 0) => Some(Direction::Left),
+            (-1, -1) => Some(Direction::UpLeft),
+            _ => None,
         }
     }
 }
 
-impl From<Direction> for u8 {
-    fn from(direction: Direction) -> Self {
-        direction as u8
-    }
-}
-
 // *****************************************************
 
-#[derive(Debug, Clone)]
+/// An ant packed into 16 bits: health, strength, heading, role, load and a flag
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Ant(u16);
 
 impl Ant {
@@ -244,56 +126,131 @@ impl Ant {
     const STRENGTH_MASK: u16       = 0b0000_1100_0000_0000;
     const DIRECTION_MASK: u16      = 0b0000_0011_1000_0000; // 8 directions (full (enum))
     const ANT_ROLE_MASK: u16       = 0b0000_0000_0110_0000; // 4 ant roles (full (enum))
-    const CARRYING_MASK: u16       = 0b0000_0000_0001_1000; // 4 carrying options (2, 3 available)
+    const CARRYING_MASK: u16       = 0b0000_0000_0001_1000; // 4 carrying options (3 available)
     const UNIQUE_FLAGS_MASK: u16   = 0b0000_0000_0000_0111; // 7 unique flags (1-7 available)
 
+    /// An ant with every field zero: a dead worker facing up
     pub fn new() -> Self {
         Ant(0)
     }
 
+    /// A fresh, fully healed ant with the stats of its role
+    pub fn with_role(role: AntRole) -> Self {
+        let (health, strength) = match role {
+            AntRole::Worker => (1, 1),
+            AntRole::Soldier => (3, 3),
+            AntRole::Scout => (1, 0),
+            AntRole::Queen => (3, 0),
+        };
+        let mut ant = Ant::new();
+        ant.set_max_health(health);
+        ant.set_current_health(health);
+        ant.set_strength(strength);
+        ant.set_ant_role(role);
+        ant
+    }
+
     // Getters
+    /// Health when fully healed, 0 to 3
     pub fn max_health(&self) -> u8 {
         ((self.0 & Self::MAX_HEALTH_MASK) >> 14) as u8
     }
+    /// Health left, 0 to 3
     pub fn current_health(&self) -> u8 {
         ((self.0 & Self::CURRENT_HEALTH_MASK) >> 12) as u8
     }
+    /// Damage dealt per strike, 0 to 3
     pub fn strength(&self) -> u8 {
         ((self.0 & Self::STRENGTH_MASK) >> 10) as u8
     }
+    /// Where the ant is facing
     pub fn direction(&self) -> Direction {
-        Direction::from(((self.0 & Self::DIRECTION_MASK) >> 7) as u8)
+        Direction::from_bits(((self.0 & Self::DIRECTION_MASK) >> 7) as u8)
     }
+    /// The ant's job
     pub fn ant_role(&self) -> AntRole {
-        AntRole::from(((self.0 & Self::ANT_ROLE_MASK) >> 5) as u8)
+        AntRole::from_bits(((self.0 & Self::ANT_ROLE_MASK) >> 5) as u8)
     }
+    /// What the ant is holding
     pub fn carrying(&self) -> Carrying {
-        Carrying::from(((self.0 & Self::CARRYING_MASK) >> 3) as u8)
+        Carrying::from_bits(((self.0 & Self::CARRYING_MASK) >> 3) as u8)
     }
+    /// The spare flag bits
     pub fn unique_flag(&self) -> UniqueFlag {
-        UniqueFlag::from((self.0 & Self::UNIQUE_FLAGS_MASK) as u8)
+        UniqueFlag::from_bits((self.0 & Self::UNIQUE_FLAGS_MASK) as u8)
     }
 
     // Setters
+    /// Sets the health when fully healed; stats keep only their low two bits
     pub fn set_max_health(&mut self, health: u8) {
         self.0 = (self.0 & !Self::MAX_HEALTH_MASK) | (((health & 0b11) as u16) << 14);
     }
+    /// Sets the health left
     pub fn set_current_health(&mut self, health: u8) {
         self.0 = (self.0 & !Self::CURRENT_HEALTH_MASK) | (((health & 0b11) as u16) << 12);
     }
+    /// Sets the damage dealt per strike
     pub fn set_strength(&mut self, strength: u8) {
         self.0 = (self.0 & !Self::STRENGTH_MASK) | (((strength & 0b11) as u16) << 10);
     }
+    /// Turns the ant to face `direction`
     pub fn set_direction(&mut self, direction: Direction) {
         self.0 = (self.0 & !Self::DIRECTION_MASK) | ((u8::from(direction) & 0b111) as u16) << 7;
     }
+    /// Changes the ant's job, leaving its stats as they are
     pub fn set_ant_role(&mut self, role: AntRole) {
         self.0 = (self.0 & !Self::ANT_ROLE_MASK) | ((u8::from(role) & 0b11) as u16) << 5;
     }
+    /// Sets what the ant is holding
     pub fn set_carrying(&mut self, value: Carrying) {
         self.0 = (self.0 & !Self::CARRYING_MASK) | ((u8::from(value) & 0b11) as u16) << 3;
     }
+    /// Sets the spare flag bits
     pub fn set_unique_flag(&mut self, flags: UniqueFlag) {
         self.0 = (self.0 & !Self::UNIQUE_FLAGS_MASK) | (u8::from(flags) as u16);
     }
-}
\ No newline at end of file
+}
+// *****************************************************
+
+/// The wide half of an ant, kept next to the packed `Ant` unless the world runs
+/// compact. Its stats go past the packed ant's cap of 3, so roles can differ
+/// by more than the packed bits allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AntState {
+    /// Which colony the ant belongs to
+    pub colony: u8,
+    /// Health when fully healed
+    pub max_health: u8,
+    /// Health left; the ant dies at 0
+    pub health: u8,
+    /// Damage dealt per strike
+    pub strength: u8,
+    /// Spent moving and regained resting, up to `constants::ANT_MAX_ENERGY`
+    pub energy: u16,
+    /// Remembered offset from the ant back to its nest
+    pub home: (i32, i32),
+    /// Ticks lived
+    pub age: u32,
+}
+
+impl AntState {
+    /// State for a newborn `ant` of `colony`, full of energy and health, with
+    /// the stats of its role
+    pub fn new(ant: &Ant, colony: u8) -> Self {
+        let (health, strength) = match ant.ant_role() {
+            AntRole::Worker => (2, 1),
+            AntRole::Soldier => (6, 4),
+            AntRole::Scout => (1, 1),
+            AntRole::Queen => (10, 0),
+        };
+        AntState {
+            colony,
+            max_health: health,
+            health,
+            strength,
+            energy: constants::ANT_MAX_ENERGY,
+            home: (0, 0),
+            age: 0,
+        }
+    }
+}
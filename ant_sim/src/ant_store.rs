@@ -0,0 +1,178 @@
+use crate::ant::{Ant, AntState};
+
+
+/// Stable handle to an ant, valid until it dies. Slots (indices into the
+/// arrays) move around as ants are removed; ids never do.
+pub type AntId = u32;
+
+const NO_SLOT: u32 = u32::MAX;
+
+/// Struct-of-arrays ant storage: packed bitfields and u16 coordinates in
+/// parallel arrays, plus optional extended state. Removal swap-removes, so
+/// live ants stay contiguous in slots 0..len().
+pub struct AntStore {
+    ants: Vec<Ant>,
+    xs: Vec<u16>,
+    ys: Vec<u16>,
+    ids: Vec<AntId>,                 // slot -> id
+    slots: Vec<u32>,                 // id -> slot, NO_SLOT once dead
+    extended: Option<Vec<AntState>>, // slot -> state, None for compact runs
+}
+
+impl AntStore {
+    /// Largest coordinate a position can hold
+    pub const MAX_COORDINATE: usize = u16::MAX as usize;
+
+    /// An empty store with room for `capacity` ants; `extended` keeps an `AntState` per ant
+    pub fn with_capacity(capacity: usize, extended: bool) -> Self {
+        AntStore {
+            ants: Vec::with_capacity(capacity),
+            xs: Vec::with_capacity(capacity),
+            ys: Vec::with_capacity(capacity),
+            ids: Vec::with_capacity(capacity),
+            slots: Vec::with_capacity(capacity),
+            extended: extended.then(|| Vec::with_capacity(capacity)),
+        }
+    }
+
+    // Getters
+    /// Number of live ants
+    pub fn len(&self) -> usize {
+        self.ants.len()
+    }
+    /// Whether every ant has died
+    pub fn is_empty(&self) -> bool {
+        self.ants.is_empty()
+    }
+    /// The packed ant in `slot`
+    pub fn ant(&self, slot: usize) -> &Ant {
+        &self.ants[slot]
+    }
+    pub(crate) fn ant_mut(&mut self, slot: usize) -> &mut Ant {
+        &mut self.ants[slot]
+    }
+    /// Where the ant in `slot` stands
+    pub fn position(&self, slot: usize) -> (usize, usize) {
+        (self.xs[slot] as usize, self.ys[slot] as usize)
+    }
+    /// The id of the ant in `slot`
+    pub fn id(&self, slot: usize) -> AntId {
+        self.ids[slot]
+    }
+    /// Where the ant `id` is kept, None once it has died
+    pub fn slot(&self, id: AntId) -> Option<usize> {
+        match self.slots.get(id as usize) {
+            Some(&slot) if slot != NO_SLOT => Some(slot as usize),
+            _ => None,
+        }
+    }
+    /// Whether ants have an `AntState` each, i.e. the world isn't compact
+    pub fn has_extended_state(&self) -> bool {
+        self.extended.is_some()
+    }
+    /// The wide record of the ant in `slot`, None in compact worlds
+    pub fn state(&self, slot: usize) -> Option<&AntState> {
+        self.extended.as_ref().map(|states| &states[slot])
+    }
+    pub(crate) fn state_mut(&mut self, slot: usize) -> Option<&mut AntState> {
+        self.extended.as_mut().map(|states| &mut states[slot])
+    }
+    pub(crate) fn states_mut(&mut self) -> Option<&mut [AntState]> {
+        self.extended.as_deref_mut()
+    }
+
+    /// Damage dealt per strike; stats come from the wide record when there is one
+    pub fn strength(&self, slot: usize) -> u8 {
+        self.state(slot).map_or(self.ants[slot].strength(), |state| state.strength)
+    }
+
+    /// (slot, ant, position) for every live ant
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Ant, (usize, usize))> {
+        self.ants.iter().enumerate().map(|(slot, ant)| (slot, ant, self.position(slot)))
+    }
+
+    // Setters
+    /// Returns true if the ant has no health left
+    pub(crate) fn damage(&mut self, slot: usize, amount: u8) -> bool {
+        match self.extended.as_mut() {
+            Some(states) => {
+                let state = &mut states[slot];
+                state.health = state.health.saturating_sub(amount);
+                state.health == 0
+            }
+            None => {
+                let ant = &mut self.ants[slot];
+                ant.set_current_health(ant.current_health().saturating_sub(amount));
+                ant.current_health() == 0
+            }
+        }
+    }
+
+    pub(crate) fn set_position(&mut self, slot: usize, x: usize, y: usize) {
+        self.xs[slot] = x as u16;
+        self.ys[slot] = y as u16;
+    }
+
+    pub(crate) fn push(&mut self, ant: Ant, colony: u8, x: usize, y: usize) -> AntId {
+        let id = self.slots.len() as AntId;
+        if let Some(states) = self.extended.as_mut() {
+            states.push(AntState::new(&ant, colony));
+        }
+        self.slots.push(self.ants.len() as u32);
+        self.ids.push(id);
+        self.ants.push(ant);
+        self.xs.push(x as u16);
+        self.ys.push(y as u16);
+        id
+    }
+
+    /// Swap-removes the ant; the last ant moves into its slot
+    pub(crate) fn remove(&mut self, id: AntId) -> Option<(Ant, usize, usize)> {
+        let slot = self.slot(id)?;
+        let position = self.position(slot);
+        let ant = self.ants.swap_remove(slot);
+        self.xs.swap_remove(slot);
+        self.ys.swap_remove(slot);
+        self.ids.swap_remove(slot);
+        if let Some(states) = self.extended.as_mut() {
+            states.swap_remove(slot);
+        }
+
+        self.slots[id as usize] = NO_SLOT;
+        if let Some(&moved) = self.ids.get(slot) {
+            self.slots[moved as usize] = slot as u32;
+        }
+        Some((ant, position.0, position.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ant::AntRole;
+
+    #[test]
+    fn ids_survive_swap_removal() {
+        let mut store = AntStore::with_capacity(4, true);
+        let ids: Vec<AntId> = (0..4).map(|i| store.push(Ant::with_role(AntRole::Worker), 0, i, i * 2)).collect();
+
+        assert_eq!(store.remove(ids[1]), Some((Ant::with_role(AntRole::Worker), 1, 2)));
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.slot(ids[1]), None);
+        assert_eq!(store.remove(ids[1]), None);
+
+        // The last ant took over the freed slot and is still found by its id
+        assert_eq!(store.slot(ids[3]), Some(1));
+        for id in [ids[0], ids[2], ids[3]] {
+            let slot = store.slot(id).unwrap();
+            assert_eq!(store.id(slot), id);
+            assert_eq!(store.position(slot), (id as usize, id as usize * 2));
+        }
+
+        // Removing the last slot leaves nothing to move
+        store.remove(ids[2]);
+        assert_eq!(store.slot(ids[3]), Some(1));
+        assert_eq!(store.push(Ant::with_role(AntRole::Soldier), 0, 9, 9), 4);
+        assert_eq!(store.len(), 3);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::params::Params;
+use crate::world::{World, WorldSize};
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use std::time::{Duration, Instant};
+
+
+/// Settings for `ant_sim bench`
+pub struct BenchConfig {
+    /// Steps timed per world
+    pub ticks: u64,
+    /// Seed every world is generated from
+    pub seed: u64,
+    /// Threads deciding ant actions
+    pub threads: usize,
+    /// Packed ants only
+    pub compact: bool,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig { ticks: 200, seed: 1, threads: 1, compact: false }
+    }
+}
+
+// Width, height and starting ants of each benchmarked world
+const CASES: [(usize, usize, usize); 4] = [
+    (128, 32, 20),
+    (256, 64, 1_000),
+    (512, 128, 10_000),
+    (1024, 256, 50_000),
+];
+
+/// Timings for one benchmark world
+pub struct BenchResult {
+    /// World width in tiles
+    pub width: usize,
+    /// World height in tiles
+    pub height: usize,
+    /// Ants at the start
+    pub ants: usize,
+    /// Steps timed
+    pub ticks: u64,
+    /// Time taken by all of them
+    pub elapsed: Duration,
+    /// Sum of the live ant count over every tick
+    pub ant_ticks: u64,
+}
+
+impl BenchResult {
+    /// Steps per second
+    pub fn ticks_per_sec(&self) -> f64 {
+        self.ticks as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Nanoseconds per ant per step
+    pub fn ns_per_ant(&self) -> f64 {
+        self.elapsed.as_nanos() as f64 / self.ant_ticks.max(1) as f64
+    }
+}
+
+/// Times `config.ticks` steps (ants, predators, soil, weather and pheromones)
+/// on a world generated from the fixed seed
+pub fn measure(config: &BenchConfig, size: &WorldSize) -> BenchResult {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut world = World::generate(&mut rng, &Params::default(), size);
+    world.threads = config.threads;
+
+    let mut ant_ticks = 0;
+    let start = Instant::now();
+    for _ in 0..config.ticks {
+        ant_ticks += world.ants().len() as u64;
+        world.step(&mut rng);
+    }
+
+    BenchResult {
+        width: size.width,
+        height: size.height,
+        ants: size.ants,
+        ticks: config.ticks,
+        elapsed: start.elapsed(),
+        ant_ticks,
+    }
+}
+
+/// Measures each benchmark world in turn, from smallest to largest
+pub fn run(config: &BenchConfig) -> impl Iterator<Item = BenchResult> + '_ {
+    CASES.into_iter().map(|(width, height, ants)| measure(config, &WorldSize { compact: config.compact, ..WorldSize::new(width, height, ants) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_the_same_work_for_the_same_seed() {
+        let config = BenchConfig { ticks: 20, ..BenchConfig::default() };
+        let size = WorldSize::new(CASES[0].0, CASES[0].1, CASES[0].2);
+        let (first, second) = (measure(&config, &size), measure(&config, &size));
+        assert_eq!((first.width, first.height, first.ants, first.ticks), (128, 32, 20, 20));
+        assert!(first.ant_ticks >= 20 * 20 / 2, "only {} ant ticks", first.ant_ticks);
+        assert_eq!(first.ant_ticks, second.ant_ticks);
+        assert!(first.ticks_per_sec() > 0.0 && first.ns_per_ant() > 0.0);
+    }
+}
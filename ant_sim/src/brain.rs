@@ -0,0 +1,421 @@
+use crate::ant;
+use crate::constants;
+use crate::params::Params;
+use crate::world::{self, Objects, Tile, World};
+
+use rand::{Rng, RngCore};
+
+
+/// What an ant wants to do this tick. The world decides whether it succeeds.
+pub enum Action {
+    /// Stay put and regain energy
+    Rest,
+    /// Face the direction and step forward
+    Move(ant::Direction),
+    /// Turn without moving
+    Face(ant::Direction),
+    /// Pick up the food underfoot
+    PickUp,
+    /// Put down whatever the ant carries
+    Drop,
+    /// Excavate the ground straight ahead
+    Dig,
+    /// Strike an adjacent predator
+    Attack,
+}
+
+// *****************************************************
+
+/// One cell of an ant's sensing cone
+#[derive(Debug, Clone, Copy)]
+pub struct Sensed {
+    /// Which way the cell lies from the ant
+    pub direction: ant::Direction,
+    /// What is on it
+    pub object: Objects,
+    /// Food trail strength
+    pub pheromone: u8,
+    /// Home trail strength
+    pub home_pheromone: u8,
+    /// Whether the ant could step onto it
+    pub walkable: bool,
+}
+
+// *****************************************************
+
+/// Read-only view of the world from one ant's position. Offsets are relative to the ant.
+pub struct Surroundings<'a> {
+    world: &'a World,
+    x: usize,
+    y: usize,
+    state: Option<&'a ant::AntState>, // The ant's wide record, None in compact worlds
+}
+
+impl<'a> Surroundings<'a> {
+    /// The world as seen by an ant at (x, y) with the wide record `state`
+    pub fn new(world: &'a World, x: usize, y: usize, state: Option<&'a ant::AntState>) -> Self {
+        Surroundings { world, x, y, state }
+    }
+
+    fn offset(&self, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        self.world.neighbour(self.x, self.y, dx, dy)
+    }
+
+    // Getters
+    /// The ant's cell
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+    /// The ant's wide record, None in compact worlds
+    pub fn state(&self) -> Option<&'a ant::AntState> {
+        self.state
+    }
+    /// The tile (dx, dy) away, None off the edge of the world
+    pub fn tile(&self, dx: isize, dy: isize) -> Option<&'a Tile> {
+        self.offset(dx, dy).and_then(|(x, y)| self.world.get_tile(x, y))
+    }
+    /// The tile the ant stands on
+    pub fn here(&self) -> &'a Tile {
+        &self.world.grid()[self.world.idx(self.x, self.y)]
+    }
+    /// Whether the ant could step onto the cell (dx, dy) away
+    pub fn is_walkable(&self, dx: isize, dy: isize) -> bool {
+        self.offset(dx, dy).is_some_and(|(x, y)| self.world.is_walkable(x, y))
+    }
+    /// Ants on the cell (dx, dy) away, the ant itself included at (0, 0)
+    pub fn ant_count(&self, dx: isize, dy: isize) -> usize {
+        self.offset(dx, dy).map_or(0, |(x, y)| self.world.ant_count(x, y))
+    }
+    /// Whether the ant is on the nest
+    pub fn at_nest(&self) -> bool {
+        self.world.is_nest(self.x, self.y)
+    }
+    /// Whether the ant is at or below the ground surface row
+    pub fn is_underground(&self) -> bool {
+        self.y >= self.world.ground_top
+    }
+    /// Whether it is night
+    pub fn is_night(&self) -> bool {
+        self.world.environment.is_night()
+    }
+    /// Whether it is raining
+    pub fn is_raining(&self) -> bool {
+        self.world.environment.is_raining()
+    }
+
+    /// Direction of the strongest neighbouring home pheromone, if any beats this tile
+    pub fn home_direction(&self) -> Option<ant::Direction> {
+        self.world.home_direction(self.x, self.y)
+    }
+
+    /// The cells ahead, ahead-left and ahead-right of an ant facing `facing`,
+    /// in that order. Cells off the edge of the world are left out.
+    pub fn cone(&self, facing: ant::Direction) -> impl Iterator<Item = Sensed> + '_ {
+        [facing, facing.turn_left(), facing.turn_right()].into_iter().filter_map(|direction| {
+            let (dx, dy) = direction.delta();
+            let tile = self.tile(dx, dy)?;
+            Some(Sensed {
+                direction,
+                object: tile.object(),
+                pheromone: tile.pheromone(),
+                home_pheromone: tile.home_pheromone(),
+                walkable: self.is_walkable(dx, dy),
+            })
+        })
+    }
+
+    /// Walkable cone cell with the strongest food trail of at least `threshold`,
+    /// straight ahead winning ties
+    pub fn strongest_trail(&self, facing: ant::Direction, threshold: u8) -> Option<ant::Direction> {
+        let mut best: Option<Sensed> = None;
+        for sensed in self.cone(facing).filter(|sensed| sensed.walkable && sensed.pheromone >= threshold) {
+            if best.is_none_or(|best| sensed.pheromone > best.pheromone) {
+                best = Some(sensed);
+            }
+        }
+        best.map(|sensed| sensed.direction)
+    }
+
+    /// Offset to the nearest food the ant can smell within `radius`
+    pub fn smell_food(&self, radius: usize) -> Option<(isize, isize)> {
+        self.world.nearest_food(self.x, self.y, radius)
+    }
+
+    /// Walkable first step toward the nearest food in smelling range
+    pub fn toward_food(&self) -> Option<ant::Direction> {
+        let direction = self.smell_food(constants::FOOD_SMELL_RADIUS).and_then(|(dx, dy)| ant::Direction::from_delta(dx, dy))?;
+        let (dx, dy) = direction.delta();
+        self.is_walkable(dx, dy).then_some(direction)
+    }
+
+    /// Remembered offset back to the nest from path integration, if the ant keeps one
+    pub fn home_vector(&self) -> Option<(isize, isize)> {
+        self.state.map(|state| (state.home.0 as isize, state.home.1 as isize)).filter(|&home| home != (0, 0))
+    }
+
+    /// Offset to the nearest predator within `radius`
+    pub fn nearest_predator(&self, radius: usize) -> Option<(isize, isize)> {
+        self.world
+            .nearest_predator(self.x, self.y, radius)
+            .map(|predator| self.world.displacement((self.x, self.y), (predator.x, predator.y)))
+    }
+}
+
+// *****************************************************
+
+/// Decision logic for a single ant. Brains only see the world through `Surroundings`
+/// and answer with an `Action`; movement and world updates stay in `World`.
+pub trait AntBrain: Send + Sync {
+    /// Shown when the brain is picked or reloaded
+    fn name(&self) -> &str;
+    /// The ant's action this tick. Called from worker threads when the world
+    /// has more than one, so any randomness must come from `rng`.
+    fn decide(&self, view: &Surroundings, ant: &ant::Ant, rng: &mut dyn RngCore) -> Action;
+}
+
+/// Looks up a built-in brain by the name used in config
+pub fn by_name(name: &str, params: &Params) -> Option<Box<dyn AntBrain>> {
+    match name {
+        "default" => Some(Box::new(DefaultBrain::new(params.clone()))),
+        "pheromone" => Some(Box::new(DefaultBrain::with_homing(params.clone(), Homing::Pheromone))),
+        "path" => Some(Box::new(DefaultBrain::with_homing(params.clone(), Homing::PathIntegration))),
+        "wander" => Some(Box::new(WanderBrain)),
+        _ => None,
+    }
+}
+
+fn random_direction(rng: &mut dyn RngCore) -> ant::Direction {
+    ant::Direction::from_bits(rng.gen_range(0..ant::Direction::COUNT))
+}
+
+/// Random walk turning `turn_chance` % of the time, shared by the built-in brains
+pub fn wander(view: &Surroundings, ant: &ant::Ant, turn_chance: u8, rng: &mut dyn RngCore) -> Action {
+    let mut direction = ant.direction();
+    if rng.gen_range(0..100) < turn_chance {
+        direction = if rng.gen_range(0..2) == 0 { direction.turn_left() } else { direction.turn_right() };
+    }
+    let (dx, dy) = direction.delta();
+    if view.is_walkable(dx, dy) { Action::Move(direction) } else { Action::Face(random_direction(rng)) }
+}
+
+// *****************************************************
+
+/// How food carriers find their way back to the nest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Homing {
+    /// Climb the home pheromone gradient only
+    Pheromone,
+    /// Head along the remembered home vector only
+    PathIntegration,
+    /// Pheromone where there is a trail, the home vector elsewhere
+    #[default]
+    Both,
+}
+
+/// The stock behaviour: forage, bring food home, dig and dump soil, and send
+/// soldiers after predators.
+#[derive(Default)]
+pub struct DefaultBrain {
+    params: Params,
+    homing: Homing,
+}
+
+impl AntBrain for DefaultBrain {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn decide(&self, view: &Surroundings, ant: &ant::Ant, rng: &mut dyn RngCore) -> Action {
+        let here = view.here().object();
+        match ant.carrying() {
+            ant::Carrying::None if matches!(here, Objects::Food) && !view.is_raining() => return Action::PickUp,
+            ant::Carrying::Food if view.at_nest() => return Action::Drop,
+            ant::Carrying::Soil
+                if !view.is_underground()
+                    && !view.at_nest()
+                    && matches!(here, Objects::None)
+                    && rng.gen_range(0..100) < self.params.soil_drop_chance =>
+            {
+                return Action::Drop;
+            }
+            _ => {}
+        }
+
+        // Food carriers head home along the home pheromone, soldiers close in on
+        // predators, everyone else wanders
+        let heading = match (ant.carrying(), ant.ant_role()) {
+            (ant::Carrying::Food, _) => self.homeward(view),
+            (_, ant::AntRole::Soldier) => match view.nearest_predator(constants::SOLDIER_SENSE_RADIUS) {
+                Some((dx, dy)) if dx.abs() <= 1 && dy.abs() <= 1 => return Action::Attack,
+                Some((dx, dy)) => ant::Direction::from_delta(dx, dy),
+                None => None,
+            },
+            _ => None,
+        };
+        let Some(direction) = heading else {
+            return self.wander_or_dig(view, ant, rng);
+        };
+
+        let (dx, dy) = direction.delta();
+        if view.is_walkable(dx, dy) { Action::Move(direction) } else { Action::Face(random_direction(rng)) }
+    }
+}
+
+impl DefaultBrain {
+    /// The stock brain tuned by `params`, homing by both trail and memory
+    pub fn new(params: Params) -> Self {
+        DefaultBrain { params, homing: Homing::default() }
+    }
+
+    /// The stock brain finding its way home by `homing` only
+    pub fn with_homing(params: Params, homing: Homing) -> Self {
+        DefaultBrain { params, homing }
+    }
+
+    fn homeward(&self, view: &Surroundings) -> Option<ant::Direction> {
+        let by_pheromone = || {
+            view.home_direction().filter(|direction| {
+                let (dx, dy) = direction.delta();
+                view.tile(dx, dy).is_some_and(|tile| tile.home_pheromone() >= self.params.home_trail_threshold)
+            })
+        };
+        let by_path = || view.home_vector().and_then(|(dx, dy)| ant::Direction::from_delta(dx, dy));
+        match self.homing {
+            Homing::Pheromone => by_pheromone(),
+            Homing::PathIntegration => by_path(),
+            Homing::Both => by_pheromone().or_else(by_path),
+        }
+    }
+
+    // Workers facing solid ground dig into it and carry the soil out; outbound
+    // ants head for food they can smell, else steer along the strongest trail ahead
+    fn wander_or_dig(&self, view: &Surroundings, ant: &ant::Ant, rng: &mut dyn RngCore) -> Action {
+        let (dx, dy) = ant.direction().delta();
+        let ahead = view.tile(dx, dy);
+        if matches!(ant.ant_role(), ant::AntRole::Worker)
+            && matches!(ant.carrying(), ant::Carrying::None)
+            && matches!(ahead.map(world::Tile::object), Some(Objects::Obstacle))
+            && rng.gen_range(0..100) < self.params.dig_chance
+        {
+            return Action::Dig;
+        }
+        if matches!(ant.carrying(), ant::Carrying::None)
+            && let Some(direction) = view.toward_food().or_else(|| view.strongest_trail(ant.direction(), self.params.food_trail_threshold))
+        {
+            return Action::Move(direction);
+        }
+        wander(view, ant, self.params.turn_chance, rng)
+    }
+}
+
+// *****************************************************
+
+/// Aimless random walk, useful as a baseline to compare other brains against
+pub struct WanderBrain;
+
+impl AntBrain for WanderBrain {
+    fn name(&self) -> &str {
+        "wander"
+    }
+
+    fn decide(&self, view: &Surroundings, ant: &ant::Ant, rng: &mut dyn RngCore) -> Action {
+        wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng)
+    }
+}
+
+// *****************************************************
+
+/// Which brain drives which ants: one per role, falling back to the default
+pub struct Brains {
+    default: Box<dyn AntBrain>,
+    by_role: [Option<Box<dyn AntBrain>>; ant::AntRole::COUNT as usize],
+}
+
+impl Brains {
+    /// `default` for every role
+    pub fn new(default: Box<dyn AntBrain>) -> Self {
+        Brains { default, by_role: [None, None, None, None] }
+    }
+
+    /// Replaces the brain of every role without one of its own
+    pub fn set_default(&mut self, brain: Box<dyn AntBrain>) {
+        self.default = brain;
+    }
+
+    /// Gives `role` a brain of its own
+    pub fn set_for_role(&mut self, role: ant::AntRole, brain: Box<dyn AntBrain>) {
+        self.by_role[u8::from(role) as usize] = Some(brain);
+    }
+
+    /// The brain that decides for `ant`
+    pub fn for_ant(&self, ant: &ant::Ant) -> &dyn AntBrain {
+        match &self.by_role[u8::from(ant.ant_role()) as usize] {
+            Some(brain) => brain.as_ref(),
+            None => self.default.as_ref(),
+        }
+    }
+}
+
+impl Default for Brains {
+    fn default() -> Self {
+        Brains::new(Box::new(DefaultBrain::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::WorldSize;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    struct Facing(ant::Direction);
+
+    impl AntBrain for Facing {
+        fn name(&self) -> &str {
+            "facing"
+        }
+
+        fn decide(&self, _view: &Surroundings, _ant: &ant::Ant, _rng: &mut dyn RngCore) -> Action {
+            Action::Face(self.0)
+        }
+    }
+
+    #[test]
+    fn each_role_follows_its_own_brain() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut world = World::from_map(&mut rng, &Params::default(), &WorldSize::default(), &["X.........", "##########"]).unwrap();
+        world.add_ant(9, 0, ant::Ant::with_role(ant::AntRole::Soldier)).unwrap();
+        world.brains = Brains::new(Box::new(Facing(ant::Direction::Left)));
+        world.brains.set_for_role(ant::AntRole::Worker, Box::new(Facing(ant::Direction::Down)));
+        world.step(&mut rng);
+
+        let facing: Vec<_> = world.ants().iter().map(|(_, ant, _)| (ant.ant_role(), ant.direction())).collect();
+        assert_eq!(facing, vec![(ant::AntRole::Worker, ant::Direction::Down), (ant::AntRole::Soldier, ant::Direction::Left)]);
+    }
+
+    #[test]
+    fn senses_the_cells_ahead_and_food_nearby() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut world = World::from_map(&mut rng, &Params::default(), &WorldSize::default(), &[
+            "X.........",
+            "..........",
+            "..........",
+            "##########",
+        ]).unwrap();
+        for (x, y, level) in [(3, 0, 2), (3, 1, 1), (3, 2, 3)] {
+            world.get_tile_mut(x, y).unwrap().set_pheromone(level);
+        }
+        let view = Surroundings::new(&world, 2, 1, None);
+        let cone: Vec<_> = view.cone(ant::Direction::Right).map(|sensed| (sensed.direction, sensed.pheromone)).collect();
+        assert_eq!(cone, vec![(ant::Direction::Right, 1), (ant::Direction::UpRight, 2), (ant::Direction::DownRight, 3)]);
+        assert_eq!(view.strongest_trail(ant::Direction::Right, 1), Some(ant::Direction::DownRight));
+        assert_eq!(view.strongest_trail(ant::Direction::Right, 4), None);
+
+        // Food in smelling range wins over the trail
+        assert_eq!(view.toward_food(), None);
+        world.add_object(2, 2, Objects::Food);
+        let view = Surroundings::new(&world, 5, 0, None);
+        assert_eq!(view.smell_food(constants::FOOD_SMELL_RADIUS), Some((-3, 2)));
+        assert_eq!(view.toward_food(), Some(ant::Direction::DownLeft));
+    }
+}
@@ -0,0 +1,275 @@
+use ant_sim::ant::AntRole;
+use ant_sim::bench::BenchConfig;
+use ant_sim::brain::{self, Brains, DefaultBrain};
+use ant_sim::evolve::EvolveConfig;
+use ant_sim::params::Params;
+use ant_sim::rules::RuleBrain;
+use ant_sim::scenario::Scenario;
+use ant_sim::topology::Topology;
+
+use std::path::PathBuf;
+
+
+pub const DEFAULT_HEADLESS_TICKS: u64 = 1000;
+const MIN_WORLD_SIDE: usize = 8;
+const MAX_WORLD_SIDE: usize = 4096;
+
+#[derive(Default)]
+pub enum Command {
+    #[default]
+    Run,
+    Evolve(EvolveConfig),
+    Bench(BenchConfig),
+}
+
+// Which renderer draws the world, picked with --renderer
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RendererKind {
+    Tui,  // The interactive terminal view
+    Text, // TextRenderer on stdout
+    Null, // NullRenderer, for --headless
+}
+
+impl std::str::FromStr for RendererKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "tui" => Ok(RendererKind::Tui),
+            "text" => Ok(RendererKind::Text),
+            "null" => Ok(RendererKind::Null),
+            _ => Err(format!("unknown renderer '{}' (expected tui, text or null)", name)),
+        }
+    }
+}
+
+// Command-line options
+//   [evolve]              run the genetic algorithm instead of the TUI
+//   [bench]               time the simulation step on worlds of several sizes
+//   --brain NAME          brain for every ant (default, pheromone, path or wander)
+//   --brain ROLE=NAME     brain for one role (worker, soldier, scout, queen)
+//   --rules [ROLE=]PATH   behaviour rules file, reloaded when it changes
+//   --genome PATH         behaviour parameters, e.g. a champion from evolve
+//   --threads N           threads for the ant update (same results for any N)
+//   --compact             packed 16-bit ants only (no colony, age, energy or home memory)
+//   --path-noise N        % chance an ant misremembers a step of its way home
+//   --topology NAME       edges: walls (default), cylinder (left/right wrap) or torus
+// run only:
+//   --renderer NAME       tui (default), text (every tick to stdout) or null; the
+//                         last two run without the TUI (1000 ticks unless --ticks)
+//   --headless            same as --renderer null
+//   --ticks N  --seed N   stop after N ticks, seed the world
+//   --size WxH            world size in tiles (default 128x32); the TUI scrolls
+//                         larger worlds with the arrow keys or the minimap
+//   --metrics PATH        write metrics to a .csv or .jsonl file
+//   --metrics-every N     ticks between metrics rows (default 1)
+//   --events PATH         write every event as JSON lines
+//   --frames DIR          write the world as DIR/frame-<tick>.ppm images
+//   --frames-every N      ticks between frames, for --frames and --gif (default 1)
+//   --gif PATH            write the run as an animated GIF
+//   --scale N             pixels per tile edge in images (default 4); 'p' in the
+//                         TUI saves a snapshot-<tick>.ppm
+//   --scenario PATH       starting world and objectives (see scenario.rs); the
+//                         flags above still apply, but the scenario's size,
+//                         topology and map win, and its seed and ticks are
+//                         used when not given. Without the TUI the run ends
+//                         once it is decided, with exit status 3 if it failed
+//   --serve ADDR          no TUI, serve the world as line-delimited JSON over TCP
+//                         (e.g. 127.0.0.1:7878, see server.rs for the protocol)
+// evolve only (scores the built-in brain, so no --brain, --rules or --threads):
+//   --generations N  --population N  --ticks N  --seed N  --out PATH
+// bench only:
+//   --ticks N  --seed N
+pub struct Config {
+    pub command: Command,
+    pub brains: Vec<(Option<AntRole>, String)>,
+    pub rules: Vec<(Option<AntRole>, PathBuf)>,
+    pub genome: Option<PathBuf>,
+    pub threads: usize,
+    pub compact: bool,
+    pub path_noise: u8,
+    pub topology: Topology,
+    pub renderer: RendererKind,
+    pub ticks: Option<u64>,
+    pub seed: Option<u64>,
+    pub size: Option<(usize, usize)>,
+    pub metrics: Option<PathBuf>,
+    pub metrics_every: u64,
+    pub events: Option<PathBuf>,
+    pub frames: Option<PathBuf>,
+    pub frames_every: u64,
+    pub gif: Option<PathBuf>,
+    pub scale: usize,
+    pub scenario: Option<PathBuf>,
+    pub serve: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            command: Command::default(),
+            brains: Vec::new(),
+            rules: Vec::new(),
+            genome: None,
+            threads: 1,
+            compact: false,
+            path_noise: 0,
+            topology: Topology::Walls,
+            renderer: RendererKind::Tui,
+            ticks: None,
+            seed: None,
+            size: None,
+            metrics: None,
+            metrics_every: 1,
+            events: None,
+            frames: None,
+            frames_every: 1,
+            gif: None,
+            scale: 4,
+            scenario: None,
+            serve: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut args = args.peekable();
+
+        match args.peek().map(String::as_str) {
+            Some("evolve") => config.command = Command::Evolve(EvolveConfig::default()),
+            Some("bench") => config.command = Command::Bench(BenchConfig::default()),
+            _ => {}
+        }
+        if !matches!(config.command, Command::Run) {
+            args.next();
+        }
+
+        while let Some(arg) = args.next() {
+            match (arg.as_str(), &mut config.command) {
+                ("--generations", Command::Evolve(evolve)) => evolve.generations = number(&mut args, &arg)?,
+                ("--population", Command::Evolve(evolve)) => evolve.population = number(&mut args, &arg)?,
+                ("--ticks", Command::Evolve(evolve)) => evolve.ticks = number(&mut args, &arg)?,
+                ("--seed", Command::Evolve(evolve)) => evolve.seed = number(&mut args, &arg)?,
+                ("--ticks", Command::Bench(bench)) => bench.ticks = number(&mut args, &arg)?,
+                ("--ticks", Command::Run) => config.ticks = Some(number(&mut args, &arg)?),
+                ("--seed", Command::Run) => config.seed = Some(number(&mut args, &arg)?),
+                ("--size", Command::Run) => config.size = Some(parse_size(&args.next().ok_or("--size needs WIDTHxHEIGHT")?)?),
+                ("--headless", Command::Run) => config.renderer = RendererKind::Null,
+                ("--renderer", Command::Run) => {
+                    let value = args.next().ok_or("--renderer needs a name")?;
+                    config.renderer = value.parse::<RendererKind>()?;
+                }
+                ("--seed", Command::Bench(bench)) => bench.seed = number(&mut args, &arg)?,
+                ("--out", Command::Evolve(evolve)) => evolve.out = PathBuf::from(args.next().ok_or("--out needs a path")?),
+                // Evolution scores the built-in brain, one genome per core
+                ("--brain" | "--rules" | "--threads", Command::Evolve(_)) => return Err(format!("{} can't be used with evolve", arg)),
+                ("--threads", _) => config.threads = number::<usize>(&mut args, &arg)?.max(1),
+                ("--compact", _) => config.compact = true,
+                ("--serve", Command::Run) => config.serve = Some(args.next().ok_or("--serve needs an address")?),
+                ("--events", Command::Run) => config.events = Some(PathBuf::from(args.next().ok_or("--events needs a path")?)),
+                ("--metrics", Command::Run) => config.metrics = Some(PathBuf::from(args.next().ok_or("--metrics needs a path")?)),
+                ("--metrics-every", Command::Run) => config.metrics_every = number::<u64>(&mut args, &arg)?.max(1),
+                ("--frames", Command::Run) => config.frames = Some(PathBuf::from(args.next().ok_or("--frames needs a directory")?)),
+                ("--frames-every", Command::Run) => config.frames_every = number::<u64>(&mut args, &arg)?.max(1),
+                ("--gif", Command::Run) => config.gif = Some(PathBuf::from(args.next().ok_or("--gif needs a path")?)),
+                ("--scale", Command::Run) => config.scale = number::<usize>(&mut args, &arg)?.clamp(1, 64),
+                ("--scenario", Command::Run) => config.scenario = Some(PathBuf::from(args.next().ok_or("--scenario needs a path")?)),
+                ("--topology", _) => {
+                    let value = args.next().ok_or("--topology needs a name")?;
+                    config.topology = value.parse::<Topology>()?;
+                }
+                ("--path-noise", _) => config.path_noise = number::<u8>(&mut args, &arg)?.min(100),
+                ("--genome", _) => config.genome = Some(PathBuf::from(args.next().ok_or("--genome needs a path")?)),
+                ("--brain", _) => {
+                    let value = args.next().ok_or("--brain needs a value")?;
+                    config.brains.push(match value.split_once('=') {
+                        Some((role, name)) => (Some(role.parse::<AntRole>().map_err(|e| e.to_string())?), name.to_string()),
+                        None => (None, value),
+                    });
+                }
+                ("--rules", _) => {
+                    let value = args.next().ok_or("--rules needs a path")?;
+                    config.rules.push(match value.split_once('=') {
+                        Some((role, path)) => (Some(role.parse::<AntRole>().map_err(|e| e.to_string())?), PathBuf::from(path)),
+                        None => (None, PathBuf::from(value)),
+                    });
+                }
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+        match &mut config.command {
+            Command::Bench(bench) => {
+                bench.threads = config.threads;
+                bench.compact = config.compact;
+            }
+            Command::Evolve(evolve) => {
+                evolve.topology = config.topology;
+                evolve.compact = config.compact;
+                evolve.path_noise = config.path_noise;
+            }
+            Command::Run => {}
+        }
+        Ok(config)
+    }
+
+    pub fn params(&self) -> Result<Params, String> {
+        match &self.genome {
+            Some(path) => Params::load(path),
+            None => Ok(Params::default()),
+        }
+    }
+
+    // Loads the scenario, if there is one, and takes its settings into the config
+    pub fn load_scenario(&mut self) -> Result<Option<Scenario>, String> {
+        let Some(path) = &self.scenario else {
+            return Ok(None);
+        };
+        let scenario = Scenario::load(path)?;
+        if let Some((width, height)) = scenario.size {
+            if ![width, height].iter().all(|side| (MIN_WORLD_SIDE..=MAX_WORLD_SIDE).contains(side)) {
+                return Err(format!("{}: size must be between {1}x{1} and {2}x{2}", path.display(), MIN_WORLD_SIDE, MAX_WORLD_SIDE));
+            }
+            self.size = Some((width, height));
+        }
+        self.topology = scenario.topology.unwrap_or(self.topology);
+        self.seed = self.seed.or(scenario.seed);
+        self.ticks = self.ticks.or(scenario.ticks);
+        Ok(Some(scenario))
+    }
+
+    pub fn build_brains(&self, params: &Params) -> Result<Brains, String> {
+        let mut brains = Brains::new(Box::new(DefaultBrain::new(params.clone())));
+        for (role, name) in &self.brains {
+            let brain = brain::by_name(name, params).ok_or_else(|| format!("Unknown brain '{}'", name))?;
+            match role {
+                Some(role) => brains.set_for_role(*role, brain),
+                None => brains.set_default(brain),
+            }
+        }
+        for (role, path) in &self.rules {
+            let brain = Box::new(RuleBrain::load(path)?);
+            match role {
+                Some(role) => brains.set_for_role(*role, brain),
+                None => brains.set_default(brain),
+            }
+        }
+        Ok(brains)
+    }
+}
+
+// "WIDTHxHEIGHT", each between MIN_WORLD_SIDE and MAX_WORLD_SIDE
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let error = || format!("--size expects WIDTHxHEIGHT between {0}x{0} and {1}x{1}, got '{2}'", MIN_WORLD_SIDE, MAX_WORLD_SIDE, value);
+    let (width, height) = value.split_once(['x', 'X']).ok_or_else(error)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if [width, height].iter().all(|side| (MIN_WORLD_SIDE..=MAX_WORLD_SIDE).contains(side)) => Ok((width, height)),
+        _ => Err(error()),
+    }
+}
+
+fn number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T, String> {
+    let value = args.next().ok_or_else(|| format!("{} needs a number", flag))?;
+    value.parse().map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+}
@@ -1,6 +1,58 @@
+/// Height of the default world, in tiles
 pub const SIMULATION_HEIGHT: usize = 32;
+/// Width of the default world, in tiles
 pub const SIMULATION_WIDTH: usize = 128;
+/// Rows of ground at the bottom of the default world
 pub const GROUND_HEIGHT : usize = 10;
 
+/// % chance each open tile starts out with food
 pub const FOOD_SPAWNING_CHANCE_PERCENTAGE: u8 = 1;
-pub const STARTING_ANT_COUNT: u8 = 5;
\ No newline at end of file
+/// Ants in the default world
+pub const STARTING_ANT_COUNT: usize = 20;
+/// % of new ants that are soldiers
+pub const SOLDIER_PERCENTAGE: u8 = 20;
+
+/// Most ants that fit on one tile
+pub const MAX_ANTS_PER_TILE: usize = 2;
+/// % chance per tick a wandering ant turns
+pub const ANT_TURN_CHANCE_PERCENTAGE: u8 = 20;
+
+/// % chance a worker digs into ground it faces
+pub const DIG_CHANCE_PERCENTAGE: u8 = 10;
+/// % chance per tick a soil carrier dumps its load above ground
+pub const SOIL_DROP_CHANCE_PERCENTAGE: u8 = 10;
+/// Ticks between each pheromone level fading by one
+pub const PHEROMONE_DECAY_TICKS: u64 = 20;
+/// Weakest food trail an outbound ant follows
+pub const FOOD_TRAIL_THRESHOLD: u8 = 1;
+
+/// Ticks in a day, the second half of which is night
+pub const DAY_LENGTH_TICKS: u64 = 600;
+/// % of ants that act on each tick of the night
+pub const NIGHT_ACTIVITY_PERCENTAGE: u8 = 25;
+/// Chance in a thousand per clear tick that it starts raining
+pub const RAIN_CHANCE_PER_MILLE: u16 = 2;
+/// How long rain lasts
+pub const RAIN_DURATION_TICKS: u32 = 80;
+/// % chance per tick each tile of water drains away while it isn't raining
+pub const WATER_DRAIN_CHANCE_PERCENTAGE: u8 = 5;
+
+/// Spiders in a new world
+pub const SPIDER_COUNT: u8 = 1;
+/// Antlions in a new world
+pub const ANTLION_COUNT: u8 = 2;
+/// Health of a new predator
+pub const PREDATOR_HEALTH: u8 = 8;
+/// % chance per tick a spider moves
+pub const SPIDER_MOVE_CHANCE_PERCENTAGE: u8 = 50;
+/// % chance per tick a predator strikes one of the ants around it
+pub const PREDATOR_STRIKE_CHANCE_PERCENTAGE: u8 = 30;
+/// How far away soldiers notice predators
+pub const SOLDIER_SENSE_RADIUS: usize = 8;
+/// How far away ants smell food
+pub const FOOD_SMELL_RADIUS: usize = 4;
+
+/// Energy of a rested ant
+pub const ANT_MAX_ENERGY: u16 = 2000;
+/// Energy regained per tick of rest
+pub const ANT_REST_ENERGY_GAIN: u16 = 5;
@@ -1,42 +1,294 @@
-use crate::world;
-use crate::constants;
+use ant_sim::render::{self, Renderer};
+use ant_sim::world;
 
-use std::io::Write;
+use std::io::{self, Write};
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Once};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crossterm::{
-    cursor::{MoveTo},
+    cursor::{Hide, MoveTo, Show},
+    event::{DisableMouseCapture, EnableMouseCapture},
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
     terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
-    execute,
+    execute, queue,
 };
+use signal_hook::consts::{SIGINT, SIGTERM};
 
 
+pub struct Palette {
+    pub empty: Color,
+    pub ant: Color,
+    pub food: Color,
+    pub ground: Color,
+    pub soil: Color,
+    pub water: Color,
+    pub predator: Color,
+}
+
+impl Palette {
+    pub const DAY: Palette = Palette {
+        empty: Color::Grey,
+        ant: Color::Red,
+        food: Color::Green,
+        ground: Color::DarkYellow,
+        soil: Color::Yellow,
+        water: Color::Blue,
+        predator: Color::Magenta,
+    };
+
+    pub const NIGHT: Palette = Palette {
+        empty: Color::DarkGrey,
+        ant: Color::DarkRed,
+        food: Color::DarkGreen,
+        ground: Color::DarkGrey,
+        soil: Color::DarkYellow,
+        water: Color::DarkBlue,
+        predator: Color::DarkMagenta,
+    };
+
+    // Colour of (x, y): the ant colour if any stand there, else the tile's
+    pub fn color_at(&self, world: &world::World, x: usize, y: usize) -> Color {
+        match world.ant_count(x, y) {
+            0 => self.tile_color(&world.grid()[world.idx(x, y)]),
+            _ => self.ant,
+        }
+    }
+
+    pub fn tile_color(&self, tile: &world::Tile) -> Color {
+        match tile.object() {
+            world::Objects::Obstacle => self.ground,
+            world::Objects::Food => self.food,
+            world::Objects::Soil => self.soil,
+            world::Objects::Water => self.water,
+            world::Objects::Predator => self.predator,
+            _ => self.empty,
+        }
+    }
+}
+
+// How much a tile stands out when several share one Braille cell; 0 leaves its dot off
+fn busyness(world: &world::World, x: usize, y: usize) -> u8 {
+    if world.ant_count(x, y) > 0 {
+        return 6;
+    }
+    match world.grid()[world.idx(x, y)].object() {
+        world::Objects::Predator => 5,
+        world::Objects::Food => 4,
+        world::Objects::Water => 3,
+        world::Objects::Soil => 2,
+        world::Objects::None => 0,
+        _ => 1,
+    }
+}
+
+// *****************************************************
+
+// Set while the terminal is in raw mode on the alternate screen
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Puts the terminal in raw mode on the alternate screen, capturing the mouse,
+// and puts it back when dropped, which also happens while unwinding from a panic
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn enter() -> io::Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen, Hide, EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// Leaves raw mode and the alternate screen; does nothing if already restored
+pub fn restore_terminal() {
+    if TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), DisableMouseCapture, ResetColor, Show, LeaveAlternateScreen);
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+// Restores the terminal before the panic message is printed, so it lands on
+// the normal screen instead of the one about to be torn down
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+    });
+}
+
+// *****************************************************
+
+
+// How many tiles each terminal cell shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellMode {
+    Glyphs,     // One tile per cell, drawn with its glyph
+    HalfBlocks, // Two tiles stacked in a '▀', top as foreground and bottom as background
+    Braille,    // 2x4 tiles as the dots of a Braille pattern, in the colour of the busiest tile
+}
+
+impl CellMode {
+    pub fn next(self) -> Self {
+        match self {
+            CellMode::Glyphs => CellMode::HalfBlocks,
+            CellMode::HalfBlocks => CellMode::Braille,
+            CellMode::Braille => CellMode::Glyphs,
+        }
+    }
+
+    // (columns, rows) of tiles in one terminal cell
+    pub fn tiles_per_cell(self) -> (usize, usize) {
+        match self {
+            CellMode::Glyphs => (1, 1),
+            CellMode::HalfBlocks => (1, 2),
+            CellMode::Braille => (2, 4),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellMode::Glyphs => "glyphs",
+            CellMode::HalfBlocks => "half blocks",
+            CellMode::Braille => "Braille",
+        }
+    }
+}
+
+// *****************************************************
+
+// Largest minimap, in terminal cells
+const MINIMAP_COLUMNS: usize = 32;
+const MINIMAP_ROWS: usize = 8;
+
+// A rectangle of the world, in tiles
+#[derive(Debug, Clone, Copy, Default)]
+pub struct View {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Where the minimap was last drawn, so clicks on it can be mapped back to tiles
+#[derive(Debug, Clone, Copy)]
+struct Minimap {
+    left: u16,
+    top: u16,
+    columns: usize,
+    rows: usize,
+    block: (usize, usize), // Tiles per minimap cell
+}
+
 pub struct Display {
     stdout: std::io::Stdout,
+    mode: CellMode,
+    camera: (usize, usize), // Top left tile of the view
+    view: View,             // What the last frame showed
+    minimap: Option<Minimap>,
+    terminal_size: (u16, u16),
+    header_rows: u16,  // Rows above the grid: the message lines and the status line
+    needs_clear: bool, // The next frame is laid out differently, so clear first
+    stop: Arc<AtomicBool>, // Set by SIGINT or SIGTERM from outside the terminal
 }
 
 impl Display {
-    pub fn new() -> Self { 
-        Display { stdout: std::io::stdout() }
+    pub fn new() -> Self {
+        Display {
+            stdout: std::io::stdout(),
+            mode: CellMode::Glyphs,
+            camera: (0, 0),
+            view: View::default(),
+            minimap: None,
+            terminal_size: (0, 0),
+            header_rows: 2,
+            needs_clear: false,
+            stop: Arc::new(AtomicBool::new(false)),
+        }
     }
 
-    pub fn run_program(&mut self, function: fn(&mut Display) -> Result<(), std::io::Error>) -> Result<(), std::io::Error> {
-        crossterm::terminal::enable_raw_mode().unwrap();
-        execute!(self.stdout, EnterAlternateScreen).unwrap();
+    // Getters
+    pub fn mode(&self) -> CellMode {
+        self.mode
+    }
+    // Whether the program was asked to stop by a signal; the loop should return
+    // so the terminal is restored on the way out
+    pub fn stop_requested(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
 
-        let result: Result<(), _> = function(self);
+    // Setters
+    pub fn set_mode(&mut self, mode: CellMode) {
+        self.needs_clear |= mode != self.mode;
+        self.mode = mode;
+    }
 
-        execute!(self.stdout, LeaveAlternateScreen).unwrap();
-        crossterm::terminal::disable_raw_mode().unwrap();
+    // Moves the camera by (dx, dy) quarters of the view; it is kept inside the world when drawn
+    pub fn pan(&mut self, dx: isize, dy: isize) {
+        let step_x = (self.view.width / 4).max(1) as isize;
+        let step_y = (self.view.height / 4).max(1) as isize;
+        self.camera.0 = self.camera.0.saturating_add_signed(dx * step_x);
+        self.camera.1 = self.camera.1.saturating_add_signed(dy * step_y);
+    }
 
-        self.clear_screen()?;
-        println!("Goodbye.");
+    // Centres the camera on the tile under a click on the minimap. Returns false
+    // if the click was anywhere else.
+    pub fn click(&mut self, column: u16, row: u16) -> bool {
+        let Some(minimap) = self.minimap else {
+            return false;
+        };
+        let (cell_x, cell_y) = (column.wrapping_sub(minimap.left) as usize, row.wrapping_sub(minimap.top) as usize);
+        if cell_x >= minimap.columns || cell_y >= minimap.rows {
+            return false;
+        }
+        let (tile_x, tile_y) = (cell_x * minimap.block.0 + minimap.block.0 / 2, cell_y * minimap.block.1 + minimap.block.1 / 2);
+        self.camera = (tile_x.saturating_sub(self.view.width / 2), tile_y.saturating_sub(self.view.height / 2));
+        true
+    }
 
-        if let Err(err) = result {
-            eprintln!("Simulation error: {}", err);
+    // SIGINT and SIGTERM only raise `stop_requested`, so the program can leave
+    // through the same path as 'q'; a second one kills it outright, terminal and all
+    fn stop_on_signals(&self) -> io::Result<()> {
+        for signal in [SIGINT, SIGTERM] {
+            signal_hook::flag::register_conditional_shutdown(signal, 1, Arc::clone(&self.stop))?;
+            signal_hook::flag::register(signal, Arc::clone(&self.stop))?;
         }
         Ok(())
     }
 
+    // Runs `function` with the terminal set up for drawing. The terminal is
+    // restored however it ends, and only then is any error or panic reported.
+    // `function` should return once `stop_requested` is set.
+    pub fn run_program(&mut self, function: impl FnOnce(&mut Display) -> Result<(), std::io::Error>) -> Result<(), std::io::Error> {
+        install_panic_hook();
+        self.stop_on_signals()?;
+        let result = {
+            let _guard = TerminalGuard::enter()?;
+            let display = &mut *self;
+            std::panic::catch_unwind(AssertUnwindSafe(move || function(display)))
+        };
+
+        match result {
+            Ok(result) => {
+                self.clear_screen()?;
+                println!("Goodbye.");
+                result
+            }
+            Err(_) => Err(io::Error::other("the simulation panicked")),
+        }
+    }
+
     pub fn clear_screen(&mut self) -> Result<(), std::io::Error> {
         execute!(
             self.stdout,
@@ -46,30 +298,271 @@ impl Display {
         Ok(())
     }
 
-    pub fn display_tile(tile: &world::Tile) -> char {
-        match tile.object() {
-            world::Objects::Obstacle => '#',
-            world::Objects::Ant => 'X',
-            world::Objects::Food => 'O',
-            _ => '.',
+    // The part of the world that fits below the header, with the camera pulled
+    // back inside the world if it has drifted off the edge
+    fn fit_view(&mut self, world: &world::World, columns: u16, rows: u16) -> View {
+        let (tiles_x, tiles_y) = self.mode.tiles_per_cell();
+        let width = world.width.min(columns as usize * tiles_x);
+        let height = world.height.min(rows.saturating_sub(self.header_rows) as usize * tiles_y).max(1);
+        self.camera = (self.camera.0.min(world.width - width), self.camera.1.min(world.height - height));
+        View { x: self.camera.0, y: self.camera.1, width, height }
+    }
+
+    fn draw_glyphs(&mut self, world: &world::World, view: View, palette: &Palette) -> Result<(), std::io::Error> {
+        let mut current_color = None;
+        for row in 0..view.height {
+            queue!(self.stdout, MoveTo(0, row as u16 + self.header_rows))?;
+            for x in view.x..view.x + view.width {
+                let y = view.y + row;
+                let (ch, color) = if world.ant_count(x, y) == 0 {
+                    let tile = &world.grid()[world.idx(x, y)];
+                    (tile.glyph(), palette.tile_color(tile))
+                } else {
+                    ('X', palette.ant)
+                };
+                if current_color != Some(color) {
+                    queue!(self.stdout, SetForegroundColor(color))?;
+                    current_color = Some(color);
+                }
+                queue!(self.stdout, Print(ch))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_half_blocks(&mut self, world: &world::World, view: View, palette: &Palette) -> Result<(), std::io::Error> {
+        let mut current_colors = None;
+        for row in 0..view.height.div_ceil(2) {
+            queue!(self.stdout, MoveTo(0, row as u16 + self.header_rows))?;
+            let y = view.y + row * 2;
+            for x in view.x..view.x + view.width {
+                let top = palette.color_at(world, x, y);
+                let bottom = if y + 1 < view.y + view.height { palette.color_at(world, x, y + 1) } else { Color::Reset };
+                if current_colors != Some((top, bottom)) {
+                    queue!(self.stdout, SetForegroundColor(top), SetBackgroundColor(bottom))?;
+                    current_colors = Some((top, bottom));
+                }
+                queue!(self.stdout, Print('▀'))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_braille(&mut self, world: &world::World, view: View, palette: &Palette) -> Result<(), std::io::Error> {
+        // Dot bit for each (column, row) of a 2x4 Braille cell
+        const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+        let mut current_color = None;
+        for row in 0..view.height.div_ceil(4) {
+            queue!(self.stdout, MoveTo(0, row as u16 + self.header_rows))?;
+            for column in 0..view.width.div_ceil(2) {
+                let (mut dots, mut busiest) = (0, None);
+                for (dx, column_dots) in DOTS.iter().enumerate() {
+                    for (dy, dot) in column_dots.iter().enumerate() {
+                        let (x, y) = (column * 2 + dx, row * 4 + dy);
+                        if x >= view.width || y >= view.height {
+                            continue;
+                        }
+                        let (x, y) = (view.x + x, view.y + y);
+                        let rank = busyness(world, x, y);
+                        if rank > 0 {
+                            dots |= dot;
+                        }
+                        if busiest.is_none_or(|(best, _)| rank > best) {
+                            busiest = Some((rank, (x, y)));
+                        }
+                    }
+                }
+                let color = busiest.map_or(palette.empty, |(_, (x, y))| palette.color_at(world, x, y));
+                if current_color != Some(color) {
+                    queue!(self.stdout, SetForegroundColor(color))?;
+                    current_color = Some(color);
+                }
+                queue!(self.stdout, Print(char::from_u32(0x2800 + dots).unwrap_or(' ')))?;
+            }
         }
+        Ok(())
     }
 
-    pub fn display_world(&mut self, world: &world::World) -> Result<(), std::io::Error> {
-        for row in world.grid.chunks(constants::SIMULATION_WIDTH) {
-            for tile in row {
-                let ch = Self::display_tile(tile);
-                write!(self.stdout, "{}", ch)?;
+    // A downsampled map of the whole world in the top right corner. Each cell
+    // shows ant density in red, or else food density in green, over the ground
+    // or sky; cells inside the camera's view are lit up.
+    fn draw_minimap(&mut self, world: &world::World, view: View, columns: u16, rows: u16) -> Result<(), std::io::Error> {
+        let max_columns = MINIMAP_COLUMNS.min(columns as usize / 3).max(1);
+        let max_rows = MINIMAP_ROWS.min(rows.saturating_sub(self.header_rows) as usize / 2).max(1);
+        let block = (world.width.div_ceil(max_columns), world.height.div_ceil(max_rows));
+        let (map_columns, map_rows) = (world.width.div_ceil(block.0), world.height.div_ceil(block.1));
+        let left = columns.saturating_sub(map_columns as u16);
+
+        let mut ants = vec![0; map_columns * map_rows];
+        for (_, _, (x, y)) in world.ants().iter() {
+            ants[(y / block.1) * map_columns + x / block.0] += 1;
+        }
+
+        for map_y in 0..map_rows {
+            queue!(self.stdout, MoveTo(left, map_y as u16 + self.header_rows))?;
+            for map_x in 0..map_columns {
+                let (x0, y0) = (map_x * block.0, map_y * block.1);
+                let (x1, y1) = ((x0 + block.0).min(world.width), (y0 + block.1).min(world.height));
+                let (mut food, mut solid) = (0, 0);
+                for y in y0..y1 {
+                    for tile in &world.grid()[world.idx(x0, y)..world.idx(x1 - 1, y) + 1] {
+                        match tile.object() {
+                            world::Objects::Food => food += 1,
+                            world::Objects::Obstacle | world::Objects::Soil => solid += 1,
+                            _ => {}
+                        }
+                    }
+                }
+                let area = (x1 - x0) * (y1 - y0);
+                let in_view = x1 > view.x && x0 < view.x + view.width && y1 > view.y && y0 < view.y + view.height;
+                let background = match (solid * 2 > area, in_view) {
+                    (true, true) => Color::DarkYellow,
+                    (true, false) => Color::Rgb { r: 70, g: 50, b: 20 },
+                    (false, true) => Color::DarkGrey,
+                    (false, false) => Color::Black,
+                };
+                let (ch, color) = match ants[map_y * map_columns + map_x] {
+                    0 => (density(food, area), Color::Green),
+                    count => (density(count, area), Color::Red),
+                };
+                queue!(self.stdout, SetBackgroundColor(background), SetForegroundColor(color), Print(ch))?;
             }
-            writeln!(self.stdout)?;
         }
+        queue!(self.stdout, ResetColor)?;
 
-        self.stdout.flush()?;
+        self.minimap = Some(Minimap { left, top: self.header_rows, columns: map_columns, rows: map_rows, block });
         Ok(())
     }
+}
+
+// How crowded `count` things make a minimap cell of `area` tiles look
+fn density(count: usize, area: usize) -> char {
+    match count {
+        0 => ' ',
+        _ if count * 8 < area => '.',
+        _ if count * 3 < area => ':',
+        _ => '*',
+    }
+}
+
+impl Renderer for Display {
+    // Every row is placed with MoveTo, since newlines in raw mode don't return
+    // the cursor to the left edge
+    fn draw(&mut self, world: &world::World, message: &str) -> Result<(), std::io::Error> {
+        // Not a terminal (or one that won't say): draw the whole world
+        let (columns, rows) = match crossterm::terminal::size() {
+            Ok((columns, rows)) if columns > 0 && rows > 0 => (columns, rows),
+            _ => (u16::MAX, u16::MAX),
+        };
+        let lines: Vec<&str> = if message.is_empty() { vec![""] } else { message.lines().collect() };
+        let header_rows = lines.len() as u16 + 1;
+        if std::mem::take(&mut self.needs_clear) || self.terminal_size != (columns, rows) || self.header_rows != header_rows {
+            queue!(self.stdout, Clear(ClearType::All))?;
+            self.terminal_size = (columns, rows);
+            self.header_rows = header_rows;
+        }
+        let width = (columns as usize).min(120);
+        let status = render::status_line(world);
+        for (row, line) in lines.into_iter().chain([status.as_str()]).enumerate() {
+            queue!(self.stdout, MoveTo(0, row as u16), Print(format!("{:<width$.width$}", line)))?;
+        }
+
+        let view = self.fit_view(world, columns, rows);
+        let palette = if world.environment.is_night() { &Palette::NIGHT } else { &Palette::DAY };
+        match self.mode {
+            CellMode::Glyphs => self.draw_glyphs(world, view, palette)?,
+            CellMode::HalfBlocks => self.draw_half_blocks(world, view, palette)?,
+            CellMode::Braille => self.draw_braille(world, view, palette)?,
+        }
+        queue!(self.stdout, ResetColor)?;
+
+        self.minimap = None;
+        if view.width < world.width || view.height < world.height {
+            self.draw_minimap(world, view, columns, rows)?;
+        }
+        self.view = view;
+
+        self.stdout.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_panic_restores_the_terminal_before_unwinding() {
+        install_panic_hook();
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        let result = std::panic::catch_unwind(|| {
+            panic!("simulation failed");
+        });
+        assert!(result.is_err());
+        assert!(!TERMINAL_ACTIVE.load(Ordering::SeqCst));
+
+        // A second restore, as the guard's drop would do, is a no-op
+        restore_terminal();
+        assert!(!TERMINAL_ACTIVE.load(Ordering::SeqCst));
+    }
+
+    fn world(width: usize, height: usize) -> world::World {
+        use rand::{SeedableRng, rngs::StdRng};
+        world::World::generate(&mut StdRng::seed_from_u64(1), &ant_sim::params::Params::default(), &world::WorldSize::new(width, height, 10))
+    }
+
+    #[test]
+    fn denser_modes_fit_more_of_the_world() {
+        let world = world(100, 40);
+        let mut display = Display::new();
+        let mut fits = Vec::new();
+        for mode in [CellMode::Glyphs, CellMode::HalfBlocks, CellMode::Braille] {
+            display.set_mode(mode);
+            let view = display.fit_view(&world, 20, 12);
+            fits.push((view.width, view.height));
+        }
+        // 20x10 cells below the two header rows
+        assert_eq!(fits, vec![(20, 10), (20, 20), (40, 40)]);
+        assert_eq!(CellMode::Braille.next(), CellMode::Glyphs);
+
+        // A camera past the edge is pulled back so the view stays full
+        display.camera = (1000, 1000);
+        let view = display.fit_view(&world, 20, 12);
+        assert_eq!((view.x, view.y), (60, 0));
+    }
 
-    // Getter
-    pub fn stdout_mut(&mut self) -> &mut std::io::Stdout {
-        &mut self.stdout
+    #[test]
+    fn minimap_clicks_centre_the_camera() {
+        let mut display = Display::new();
+        assert!(!display.click(12, 3));
+
+        display.view = View { x: 0, y: 0, width: 20, height: 10 };
+        display.minimap = Some(Minimap { left: 10, top: 2, columns: 5, rows: 2, block: (20, 20) });
+        // Cell (2, 1) covers tiles 40..60 by 20..40
+        assert!(display.click(12, 3));
+        assert_eq!(display.camera, (40, 25));
+
+        // Left of, right of and below the minimap
+        assert!(!display.click(9, 3));
+        assert!(!display.click(15, 3));
+        assert!(!display.click(12, 4));
+        assert_eq!(display.camera, (40, 25));
+    }
+
+    #[test]
+    fn a_signal_asks_the_display_to_stop() {
+        let display = Display::new();
+        display.stop_on_signals().unwrap();
+        assert!(!display.stop_requested());
+        signal_hook::low_level::raise(SIGTERM).unwrap();
+        assert!(display.stop_requested());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn density_grows_with_the_count() {
+        assert_eq!(density(0, 100), ' ');
+        assert_eq!(density(1, 100), '.');
+        assert_eq!(density(20, 100), ':');
+        assert_eq!(density(50, 100), '*');
+    }
+}
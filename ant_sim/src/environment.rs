@@ -0,0 +1,61 @@
+use crate::constants;
+
+
+/// Rain floods tunnel entrances and keeps ants from picking up food
+pub enum Weather {
+    /// No rain
+    Clear,
+    /// Raining for `remaining` more ticks
+    Rain {
+        /// Ticks until it clears
+        remaining: u32,
+    },
+}
+
+// *****************************************************
+
+/// The clock (day and night follow from the tick) and the weather
+pub struct Environment {
+    tick: u64,
+    weather: Weather,
+}
+
+impl Environment {
+    /// Tick 0, the start of a clear day
+    pub fn new() -> Self {
+        Environment { tick: 0, weather: Weather::Clear }
+    }
+
+    // Getters
+    /// Ticks since the world began
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    /// The second half of each day is night
+    pub fn is_night(&self) -> bool {
+        self.tick % constants::DAY_LENGTH_TICKS >= constants::DAY_LENGTH_TICKS / 2
+    }
+    /// Whether it is raining
+    pub fn is_raining(&self) -> bool {
+        matches!(self.weather, Weather::Rain { .. })
+    }
+
+    /// Advances the clock by one tick and rolls the weather
+    pub fn advance(&mut self, rng: &mut impl rand::Rng) {
+        self.tick += 1;
+        self.weather = match self.weather {
+            Weather::Rain { remaining: 0 } => Weather::Clear,
+            Weather::Rain { remaining } => Weather::Rain { remaining: remaining - 1 },
+            Weather::Clear if rng.gen_range(0..1000) < constants::RAIN_CHANCE_PER_MILLE => {
+                Weather::Rain { remaining: constants::RAIN_DURATION_TICKS }
+            }
+            Weather::Clear => Weather::Clear,
+        };
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,123 @@
+use crate::ant::AntRole;
+use crate::ant_store::AntId;
+use crate::json::Json;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+
+/// What kind of thing happened
+pub enum EventKind {
+    /// A new ant joined the colony
+    AntSpawned {
+        /// The new ant's job
+        role: AntRole,
+    },
+    /// An ant picked up food
+    FoodPickedUp,
+    /// An ant brought food into the nest
+    FoodDelivered,
+    /// An ant dug out a tile of ground
+    TileDug,
+    /// An ant was killed
+    AntDied,
+    /// A colony's nest was set up
+    ColonyFounded {
+        /// Which colony
+        colony: u8,
+    },
+}
+
+impl EventKind {
+    /// The variant's name, as written in event logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::AntSpawned { .. } => "AntSpawned",
+            EventKind::FoodPickedUp => "FoodPickedUp",
+            EventKind::FoodDelivered => "FoodDelivered",
+            EventKind::TileDug => "TileDug",
+            EventKind::AntDied => "AntDied",
+            EventKind::ColonyFounded { .. } => "ColonyFounded",
+        }
+    }
+}
+
+/// Something that happened in the world, and where
+pub struct Event {
+    /// Tick it happened on
+    pub tick: u64,
+    /// Column it happened in
+    pub x: usize,
+    /// Row it happened in
+    pub y: usize,
+    /// The ant involved; None for events no single ant caused
+    pub ant: Option<AntId>,
+    /// What happened
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// e.g. {"tick":3,"event":"TileDug","x":64,"y":22,"ant":7}
+    pub fn to_json(&self) -> Json {
+        let mut json = Json::object([
+            ("tick", self.tick.into()),
+            ("event", self.kind.name().into()),
+            ("x", self.x.into()),
+            ("y", self.y.into()),
+        ]);
+        if let Some(ant) = self.ant {
+            json.push("ant", ant.into());
+        }
+        match self.kind {
+            EventKind::AntSpawned { role } => json.push("role", role.name().into()),
+            EventKind::ColonyFounded { colony } => json.push("colony", (colony as u64).into()),
+            _ => {}
+        }
+        json
+    }
+}
+
+// *****************************************************
+
+/// Code that wants to hear about events as they happen. Any
+/// `FnMut(&Event) + Send + Sync` closure is an observer.
+pub trait Observer: Send + Sync {
+    /// Called once per event, in the order they happened
+    fn notify(&mut self, event: &Event);
+}
+
+impl<F: FnMut(&Event) + Send + Sync> Observer for F {
+    fn notify(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+// *****************************************************
+
+/// Writes events to a file as JSON lines
+pub struct EventLog {
+    out: BufWriter<File>,
+}
+
+impl EventLog {
+    /// Starts an empty log at `path`, replacing the events of any earlier run
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(EventLog { out: BufWriter::new(file) })
+    }
+
+    /// Appends a line per event
+    pub fn write(&mut self, events: &[Event]) -> io::Result<()> {
+        for event in events {
+            writeln!(self.out, "{}", event.to_json())?;
+        }
+        Ok(())
+    }
+
+    /// Pushes buffered event lines to the file; call once the run is over so the
+    /// last tick's events aren't lost
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,154 @@
+use crate::params::Params;
+use crate::topology::Topology;
+use crate::world::{World, WorldSize};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use std::path::PathBuf;
+
+
+/// Settings for `ant_sim evolve`
+pub struct EvolveConfig {
+    /// Generations to breed
+    pub generations: usize,
+    /// Genomes per generation
+    pub population: usize,
+    /// Length of each evaluation run
+    pub ticks: u64,
+    /// Seed for breeding and for the evaluation worlds
+    pub seed: u64,
+    /// Where the champion genome is saved
+    pub out: PathBuf,
+    /// Edges of the evaluation worlds
+    pub topology: Topology,
+    /// Packed ants only in the evaluation worlds
+    pub compact: bool,
+    /// % chance an ant misremembers a step of its way home
+    pub path_noise: u8,
+}
+
+impl Default for EvolveConfig {
+    fn default() -> Self {
+        EvolveConfig {
+            generations: 20,
+            population: 24,
+            ticks: 2000,
+            seed: 1,
+            out: PathBuf::from("champion.genome"),
+            topology: Topology::Walls,
+            compact: false,
+            path_noise: 0,
+        }
+    }
+}
+
+/// Worlds each genome is scored on per generation
+pub const EVALUATION_RUNS: u64 = 3;
+const TOURNAMENT_SIZE: usize = 3;
+const ELITE_COUNT: usize = 2;
+const MUTATION_CHANCE_PERCENTAGE: u8 = 20;
+
+// Food stored over `config.ticks`, summed across the generation's evaluation worlds
+fn score(params: &Params, config: &EvolveConfig, generation_seed: u64) -> u32 {
+    (0..EVALUATION_RUNS)
+        .map(|run| {
+            let mut rng = StdRng::seed_from_u64(generation_seed.wrapping_add(run));
+            let mut world = World::generate(&mut rng, params, &WorldSize { compact: config.compact, ..WorldSize::default() });
+            world.topology = config.topology;
+            world.path_noise = config.path_noise;
+            for _ in 0..config.ticks {
+                world.step(&mut rng);
+            }
+            world.stats.food_stored
+        })
+        .sum()
+}
+
+// Scores the whole population, spread over the available cores
+fn score_all(population: &[Params], config: &EvolveConfig, generation_seed: u64) -> Vec<u32> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = population.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = population
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|params| score(params, config, generation_seed)).collect::<Vec<_>>()))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().expect("evaluation thread panicked")).collect()
+    })
+}
+
+/// How one generation scored: food stored, summed over its evaluation worlds
+pub struct GenerationScores {
+    /// Which generation, counting from 1
+    pub generation: usize,
+    /// Score of the best genome
+    pub best: u32,
+    /// Mean score over the population
+    pub mean: f64,
+}
+
+fn tournament<'a>(population: &'a [Params], scores: &[u32], rng: &mut impl Rng) -> &'a Params {
+    let best = (0..TOURNAMENT_SIZE)
+        .map(|_| rng.gen_range(0..population.len()))
+        .max_by_key(|&i| scores[i])
+        .unwrap_or(0);
+    &population[best]
+}
+
+// Uniform crossover followed by per-gene mutation within each gene's range
+fn breed(a: &Params, b: &Params, rng: &mut impl Rng) -> Params {
+    let (a, b) = (a.genes(), b.genes());
+    let mut genes = [0; Params::GENE_COUNT];
+    for (i, gene) in genes.iter_mut().enumerate() {
+        *gene = if rng.gen_range(0..2) == 0 { a[i] } else { b[i] };
+
+        if rng.gen_range(0..100) < MUTATION_CHANCE_PERCENTAGE {
+            let (_, min, max) = Params::GENES[i];
+            let spread = ((max - min) / 5).max(1) as i16;
+            *gene = (*gene as i16 + rng.gen_range(-spread..=spread)).clamp(min as i16, max as i16) as u8;
+        }
+    }
+    Params::from_genes(genes)
+}
+
+fn random_params(rng: &mut impl Rng) -> Params {
+    Params::from_genes(std::array::from_fn(|i| rng.gen_range(Params::GENES[i].1..=Params::GENES[i].2)))
+}
+
+/// Runs the genetic algorithm, saving the best genome after every generation
+/// and passing its scores to `on_generation`. Returns the champion and its score.
+pub fn run(config: &EvolveConfig, start: Params, mut on_generation: impl FnMut(&GenerationScores)) -> Result<(Params, u32), String> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let population_size = config.population.max(ELITE_COUNT + 1);
+
+    // Seed with the starting genome and fill up with random ones
+    let mut population: Vec<Params> = std::iter::once(start).chain((1..population_size).map(|_| random_params(&mut rng))).collect();
+    let mut champion = (population[0].clone(), 0);
+
+    for generation in 0..config.generations {
+        let generation_seed = config.seed.wrapping_mul(1_000_003).wrapping_add(generation as u64 * EVALUATION_RUNS);
+        let scores = score_all(&population, config, generation_seed);
+
+        let mut ranked: Vec<usize> = (0..population.len()).collect();
+        ranked.sort_by_key(|&i| std::cmp::Reverse(scores[i]));
+
+        let best = ranked[0];
+        let mean = scores.iter().sum::<u32>() as f64 / scores.len() as f64;
+        on_generation(&GenerationScores { generation: generation + 1, best: scores[best], mean });
+
+        champion = (population[best].clone(), scores[best]);
+        champion.0.save(&config.out).map_err(|e| format!("{}: {}", config.out.display(), e))?;
+
+        // Elites carry over unchanged, the rest are bred from tournament winners
+        let mut next: Vec<Params> = ranked[..ELITE_COUNT].iter().map(|&i| population[i].clone()).collect();
+        while next.len() < population_size {
+            let a = tournament(&population, &scores, &mut rng);
+            let b = tournament(&population, &scores, &mut rng);
+            next.push(breed(a, b, &mut rng));
+        }
+        population = next;
+    }
+
+    Ok(champion)
+}
@@ -0,0 +1,329 @@
+use crate::world::{Objects, World};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+
+/// Red, green and blue
+pub type Rgb = [u8; 3];
+
+// One colour per object, plus the open sky and dug tunnels
+const SKY: Rgb = [235, 235, 235];
+const TUNNEL: Rgb = [90, 65, 40];
+const GROUND: Rgb = [130, 90, 45];
+const SOIL: Rgb = [200, 160, 80];
+const FOOD: Rgb = [40, 190, 60];
+const WATER: Rgb = [50, 90, 220];
+const PREDATOR: Rgb = [190, 40, 190];
+const ANT: Rgb = [210, 30, 30];
+
+/// Most pixels a frame may have, about 200 MB of RGB
+pub const MAX_PIXELS: usize = 64 * 1024 * 1024;
+
+/// An RGB picture of the world, `scale` x `scale` pixels per tile
+pub struct Frame {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+    /// Row by row from the top left
+    pub pixels: Vec<Rgb>,
+}
+
+impl Frame {
+    /// Paints every tile of `world` as a square of `scale` pixels (at least 1).
+    /// Fails rather than allocate a frame wider or taller than a GIF allows, or
+    /// with more than `MAX_PIXELS` pixels.
+    pub fn render(world: &World, scale: usize) -> io::Result<Self> {
+        let scale = scale.max(1);
+        let (width, height) = match (world.width.checked_mul(scale), world.height.checked_mul(scale)) {
+            (Some(width), Some(height)) if width <= u16::MAX as usize && height <= u16::MAX as usize && width * height <= MAX_PIXELS => (width, height),
+            _ => {
+                let message = format!("a {}x{} world at scale {} is too large an image", world.width, world.height, scale);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+        };
+        let mut pixels = vec![[0; 3]; width * height];
+        for y in 0..world.height {
+            for x in 0..world.width {
+                let color = tile_color(world, x, y);
+                for py in y * scale..(y + 1) * scale {
+                    pixels[py * width + x * scale..py * width + (x + 1) * scale].fill(color);
+                }
+            }
+        }
+        Ok(Frame { width, height, pixels })
+    }
+
+    /// Binary PPM (P6), readable by most image tools
+    pub fn write_ppm(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in &self.pixels {
+            out.write_all(pixel)?;
+        }
+        out.flush()
+    }
+}
+
+// Objects get a flat colour; open tiles are tinted green by the food trail and
+// blue by the home trail. Everything is darker at night.
+fn tile_color(world: &World, x: usize, y: usize) -> Rgb {
+    let tile = &world.grid()[world.idx(x, y)];
+    let color = if world.ant_count(x, y) > 0 {
+        ANT
+    } else {
+        match tile.object() {
+            Objects::Obstacle => GROUND,
+            Objects::Soil => SOIL,
+            Objects::Food => FOOD,
+            Objects::Water => WATER,
+            Objects::Predator => PREDATOR,
+            _ => {
+                let base = if y >= world.ground_top { TUNNEL } else { SKY };
+                let food = tile.pheromone() as u16 * 60 / 3;
+                let home = tile.home_pheromone() as u16 * 60 / 7;
+                [
+                    (base[0] as u16).saturating_sub(food + home) as u8,
+                    (base[1] as u16).saturating_sub(home) as u8,
+                    (base[2] as u16).saturating_sub(food) as u8,
+                ]
+            }
+        }
+    };
+    if world.environment.is_night() { color.map(|channel| channel / 2) } else { color }
+}
+
+// *****************************************************
+
+/// Streams frames into an animated GIF. Colours are rounded to a fixed 6x6x6
+/// colour cube so every frame shares one palette.
+pub struct GifWriter {
+    out: BufWriter<File>,
+    width: u16,
+    height: u16,
+    delay: u16, // Hundredths of a second per frame
+}
+
+impl GifWriter {
+    /// Starts a looping GIF of `width` x `height` frames, each shown for
+    /// `delay` hundredths of a second
+    pub fn create(path: &Path, width: usize, height: usize, delay: u16) -> io::Result<Self> {
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "image too large for a GIF"));
+        };
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        out.write_all(&[0xF7, 0, 0])?; // 256-colour global palette
+        for index in 0..=255u16 {
+            let entry = match index < 216 {
+                true => [index / 36, index / 6 % 6, index % 6].map(|level| (level * 51) as u8),
+                false => [0; 3],
+            };
+            out.write_all(&entry)?;
+        }
+        // Loop forever
+        out.write_all(&[0x21, 0xFF, 0x0B])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+        Ok(GifWriter { out, width, height, delay })
+    }
+
+    /// Appends a frame, which must be the size given to `create`
+    pub fn add_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        if frame.width != self.width as usize || frame.height != self.height as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame size changed"));
+        }
+        let [delay_low, delay_high] = self.delay.to_le_bytes();
+        self.out.write_all(&[0x21, 0xF9, 0x04, 0x00, delay_low, delay_high, 0x00, 0x00])?;
+        self.out.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x00, 8])?; // No local palette, 8-bit LZW
+
+        let indices: Vec<u8> = frame.pixels.iter().map(|&pixel| cube_index(pixel)).collect();
+        for block in lzw_encode(&indices).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x00])
+    }
+
+    /// Ends the file; without this it is cut off
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&[0x3B])?;
+        self.out.flush()
+    }
+}
+
+fn cube_index(pixel: Rgb) -> u8 {
+    let [r, g, b] = pixel.map(|channel| (channel as u16 * 5 + 127) / 255);
+    (r * 36 + g * 6 + b) as u8
+}
+
+// GIF flavoured LZW: 8-bit symbols, codes packed least significant bit first,
+// growing from 9 to 12 bits, with a clear code once the table fills
+fn lzw_encode(symbols: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const MAX_CODES: u16 = 4096;
+
+    let mut bits = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = 9;
+    let mut next_code = END + 1;
+    bits.write(CLEAR, code_size);
+
+    let Some((&first, rest)) = symbols.split_first() else {
+        bits.write(END, code_size);
+        return bits.finish();
+    };
+    let mut prefix = first as u16;
+    for &symbol in rest {
+        if let Some(&code) = table.get(&(prefix, symbol)) {
+            prefix = code;
+            continue;
+        }
+        bits.write(prefix, code_size);
+        if next_code >= 1 << code_size && code_size < 12 {
+            code_size += 1;
+        }
+        if next_code < MAX_CODES {
+            table.insert((prefix, symbol), next_code);
+            next_code += 1;
+        } else {
+            bits.write(CLEAR, code_size);
+            table.clear();
+            code_size = 9;
+            next_code = END + 1;
+        }
+        prefix = symbol as u16;
+    }
+    bits.write(prefix, code_size);
+    if next_code >= 1 << code_size && code_size < 12 {
+        code_size += 1;
+    }
+    bits.write(END, code_size);
+    bits.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Params;
+    use crate::world::WorldSize;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    // A plain GIF LZW decoder to check the encoder against
+    fn lzw_decode(bytes: &[u8]) -> Vec<u8> {
+        let mut position = 0;
+        let mut read = |size: u32| {
+            let code = (0..size).fold(0, |code, bit| {
+                let index = position + bit as usize;
+                code | (((bytes[index / 8] >> (index % 8)) & 1) as u16) << bit
+            });
+            position += size as usize;
+            code
+        };
+
+        let reset = || (0..=257u16).map(|code| vec![code as u8]).collect::<Vec<_>>();
+        let mut table = reset();
+        let mut code_size = 9;
+        let mut previous: Option<u16> = None;
+        let mut output = Vec::new();
+        loop {
+            let code = read(code_size);
+            match code {
+                256 => {
+                    table = reset();
+                    code_size = 9;
+                    previous = None;
+                    continue;
+                }
+                257 => return output,
+                _ => {}
+            }
+            let entry = match previous {
+                Some(previous) if code as usize == table.len() => {
+                    let mut entry = table[previous as usize].clone();
+                    entry.push(entry[0]);
+                    entry
+                }
+                _ => table[code as usize].clone(),
+            };
+            output.extend_from_slice(&entry);
+            if let Some(previous) = previous
+                && table.len() < 4096
+            {
+                let mut added = table[previous as usize].clone();
+                added.push(entry[0]);
+                table.push(added);
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        let mut noise = 12345u32;
+        let random: Vec<u8> = (0..20_000)
+            .map(|_| {
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (noise >> 16) as u8
+            })
+            .collect();
+        let inputs = [Vec::new(), vec![7], vec![0; 10_000], b"TOBEORNOTTOBEORTOBEORNOT".repeat(50), random];
+        for input in inputs {
+            assert_eq!(lzw_decode(&lzw_encode(&input)), input, "length {}", input.len());
+        }
+    }
+
+    #[test]
+    fn refuses_frames_too_large_to_allocate() {
+        let world = World::from_map(&mut StdRng::seed_from_u64(1), &Params::default(), &WorldSize::default(), &["X...", "####"]).unwrap();
+        let frame = Frame::render(&world, 3).unwrap();
+        assert_eq!((frame.width, frame.height, frame.pixels.len()), (12, 6, 72));
+        assert!(Frame::render(&world, 20_000).is_err());
+        assert!(Frame::render(&world, usize::MAX).is_err());
+    }
+
+    #[test]
+    fn palette_cube_covers_the_corners() {
+        assert_eq!(cube_index([0, 0, 0]), 0);
+        assert_eq!(cube_index([255, 255, 255]), 215);
+        assert_eq!(cube_index([255, 0, 0]), 180);
+    }
+}
@@ -0,0 +1,74 @@
+//! An ant colony simulation on a grid of bit-packed tiles.
+//!
+//! [`World`] holds the grid, the ants and the predators; [`World::step`]
+//! advances it one tick. Each ant's behaviour comes from an [`AntBrain`],
+//! picked per role in [`Brains`], and what happens can be watched through
+//! [`World::subscribe`] or read back with [`World::events`] after a step.
+//!
+//! ```
+//! use ant_sim::{Params, World, WorldSize};
+//! use rand::{SeedableRng, rngs::StdRng};
+//!
+//! let mut rng = StdRng::seed_from_u64(1);
+//! let mut world = World::generate(&mut rng, &Params::default(), &WorldSize::default());
+//! for _ in 0..100 {
+//!     world.step(&mut rng);
+//! }
+//! assert_eq!(world.environment.tick(), 100);
+//! ```
+//!
+//! The `ant_sim` binary is the terminal front end on top of this crate; its
+//! interactive view needs the `tui` feature (on by default).
+
+#![warn(missing_docs)]
+
+/// The packed 16-bit ant, its role, load and heading, and the wide per-ant state
+pub mod ant;
+/// Struct-of-arrays storage for every live ant
+pub mod ant_store;
+/// Timing the simulation step on worlds of several sizes
+pub mod bench;
+/// What ants decide to do each tick: the brain trait and the built-in brains
+pub mod brain;
+/// Tuning constants for the world, the ants and the predators
+pub mod constants;
+/// Time of day and weather
+pub mod environment;
+/// Events emitted by the world and a JSON-lines log of them
+pub mod events;
+/// A genetic algorithm tuning `Params` for food gathered
+pub mod evolve;
+/// Pictures of the world: PPM frames and animated GIFs
+pub mod image;
+/// A minimal JSON value, writer and parser
+pub mod json;
+/// Per-tick colony metrics written as CSV or JSON lines
+pub mod metrics;
+/// Which ants stand on each cell
+pub mod occupancy;
+/// Enums that fit in a few bits, and their conversion errors
+pub mod packed;
+/// Tunable behaviour parameters, loadable from genome files
+pub mod params;
+/// Spiders and antlions
+pub mod predator;
+/// Drawing the world each tick: the renderer trait and two plain backends
+pub mod render;
+pub mod rules;
+pub mod scenario;
+pub mod server;
+/// How the edges of the world join up
+pub mod topology;
+/// The grid, the colony and the simulation step
+pub mod world;
+
+mod rng;
+
+pub use ant::{Ant, AntRole, AntState, Carrying, Direction, UniqueFlag};
+pub use ant_store::AntId;
+pub use brain::{Action, AntBrain, Brains, DefaultBrain, Surroundings};
+pub use events::{Event, EventKind, Observer};
+pub use packed::EnumError;
+pub use params::Params;
+pub use topology::Topology;
+pub use world::{Objects, Tile, World, WorldSize};
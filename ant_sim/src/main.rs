@@ -1,60 +1,328 @@
-mod ant;
-mod world;
+mod config;
+#[cfg(feature = "tui")]
 mod display;
-mod constants;
+mod recorder;
 
-use crossterm::{
-    cursor::{MoveTo},
-    event::{self, Event, KeyCode},
-    execute,
-};
+use ant_sim::render::{NullRenderer, Renderer, TextRenderer};
+use ant_sim::scenario::{Referee, Scenario};
+use ant_sim::{bench, brain, constants, evolve, params, server, world};
 
-use std::{io::{Write, Result}, time::Duration};
+#[cfg(feature = "tui")]
+use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 
+use rand::{SeedableRng, rngs::StdRng};
 
-fn run_simulation(main_display: &mut display::Display) -> Result<()> {
-    let mut rng: rand::prelude::ThreadRng = rand::thread_rng();
-    let mut world: world::World = world::World::new(&mut rng);
-    {
-        let stdout = main_display.stdout_mut();
+use std::{io::Result, time::Duration};
 
-        // // Hide cursor
-        execute!(stdout, crossterm::cursor::Hide)?;
-    }
-    loop {
-        {
-            let stdout = main_display.stdout_mut();
-            // Move cursor to top left without clearing
-            execute!(stdout, MoveTo(0, 0))?;
 
-            writeln!(stdout, "Press 'q' to quit.\n")?;
+fn create_world(
+    config: &config::Config,
+    params: &params::Params,
+    brains: brain::Brains,
+    scenario: Option<&Scenario>,
+) -> Result<(world::World, StdRng)> {
+    let mut rng = config.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+    let map = scenario.and_then(|scenario| scenario.map.as_ref());
+    let mut size = match map.map(|rows| (rows[0].chars().count(), rows.len())).or(config.size) {
+        // Keep the default density of ants
+        Some((width, height)) => {
+            let ants = constants::STARTING_ANT_COUNT * width * height / (constants::SIMULATION_WIDTH * constants::SIMULATION_HEIGHT);
+            world::WorldSize::new(width, height, ants.max(1))
+        }
+        None => world::WorldSize::default(),
+    };
+    size.ants = scenario.and_then(|scenario| scenario.ants).unwrap_or(size.ants);
+    size.compact = config.compact;
+    let mut world: world::World = match map {
+        Some(rows) => {
+            let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+            world::World::from_map(&mut rng, params, &size, &rows).map_err(std::io::Error::other)?
         }
+        None => world::World::generate(&mut rng, params, &size),
+    };
+    world.brains = brains;
+    world.threads = config.threads;
+    world.path_noise = config.path_noise;
+    world.topology = config.topology;
+    Ok((world, rng))
+}
+
+#[cfg(feature = "tui")]
+fn run_simulation(
+    main_display: &mut display::Display,
+    config: &config::Config,
+    params: &params::Params,
+    brains: brain::Brains,
+    scenario: Option<&Scenario>,
+) -> Result<()> {
+    let (mut world, mut rng) = create_world(config, params, brains, scenario)?;
+    let mut recorder = recorder::Recorder::create(config, &world)?;
+    let mut referee = scenario.map(Referee::new);
 
-        main_display.display_world(&world)?;
+    let mut rule_watchers: Vec<ant_sim::rules::RuleWatcher> =
+        config.rules.iter().map(|(role, path)| ant_sim::rules::RuleWatcher::new(*role, path.clone())).collect();
+    let mut status = String::new();
+    let genome = config.genome.as_ref().map(|path| format!("Genome {}: {}", path.display(), params.summary()));
+    'running: loop {
+        let mut message = format!("'q' quits, 'p' saves a picture, 'v' changes the view, arrows or the minimap move it. {}", status);
+        if let Some(genome) = &genome {
+            message = format!("{}\n{}", message, genome);
+        }
+        if let Some(referee) = &referee {
+            message = format!("{}\n{}", message, referee.progress(&world));
+        }
+        main_display.draw(&world, &message)?;
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Char('q') => break,
+        // Take every pending event, so mouse movement can't queue up behind the ticks
+        let mut timeout = Duration::from_millis(100);
+        while event::poll(timeout)? {
+            timeout = Duration::ZERO;
+            match event::read()? {
+                // Raw mode turns Ctrl-C into a key press rather than a signal
+                Event::Key(key_event) => match key_event.code {
+                    KeyCode::Char('q') => break 'running,
+                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => break 'running,
+                    KeyCode::Char('v') => {
+                        main_display.set_mode(main_display.mode().next());
+                        status = format!("Showing {}", main_display.mode().name());
+                    }
+                    KeyCode::Char('p') => {
+                        status = match recorder.snapshot(&world) {
+                            Ok(path) => format!("Saved {}", path.display()),
+                            Err(e) => format!("Couldn't save a picture: {}", e),
+                        };
+                    }
+                    KeyCode::Left => main_display.pan(-1, 0),
+                    KeyCode::Right => main_display.pan(1, 0),
+                    KeyCode::Up => main_display.pan(0, -1),
+                    KeyCode::Down => main_display.pan(0, 1),
                     _ => {}
+                },
+                Event::Mouse(MouseEvent { kind: MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left), column, row, .. }) => {
+                    main_display.click(column, row);
                 }
+                _ => {}
             }
         }
+
+        // Hot reload edited rule files, keeping the old rules if the new ones don't parse
+        for watcher in rule_watchers.iter_mut() {
+            match watcher.poll() {
+                Some(Ok(brain)) => {
+                    status = format!("Reloaded {}", brain::AntBrain::name(&brain));
+                    match watcher.role {
+                        Some(role) => world.brains.set_for_role(role, Box::new(brain)),
+                        None => world.brains.set_default(Box::new(brain)),
+                    }
+                }
+                Some(Err(e)) => status = e,
+                None => {}
+            }
+        }
+
+        world.step(&mut rng);
+        if let Some(referee) = referee.as_mut() {
+            referee.update(&mut world, &mut rng);
+        }
+        recorder.record(&world)?;
+        if config.ticks.is_some_and(|ticks| world.environment.tick() >= ticks) {
+            break;
+        }
+        if main_display.stop_requested() {
+            break;
+        }
         std::thread::sleep(Duration::from_millis(50));
     }
+    recorder.finish()?;
+
+    Ok(())
+}
+
+
+// Steps the world as fast as it goes, drawing each tick with `renderer`. A
+// scenario run stops once it is decided; returns whether it passed.
+fn run_headless(
+    config: &config::Config,
+    params: &params::Params,
+    brains: brain::Brains,
+    scenario: Option<&Scenario>,
+    renderer: &mut dyn Renderer,
+) -> Result<bool> {
+    let (mut world, mut rng) = create_world(config, params, brains, scenario)?;
+    let mut recorder = recorder::Recorder::create(config, &world)?;
+    let mut referee = scenario.map(Referee::new);
+    let ticks = config.ticks.or(scenario.and_then(Scenario::time_limit)).unwrap_or(config::DEFAULT_HEADLESS_TICKS);
 
-    // Show cursor
-    {
-        let stdout = main_display.stdout_mut();
-        execute!(stdout, crossterm::cursor::Show)?;
+    for _ in 0..ticks {
+        world.step(&mut rng);
+        if let Some(referee) = referee.as_mut() {
+            referee.update(&mut world, &mut rng);
+        }
+        recorder.record(&world)?;
+        renderer.draw(&world, &referee.as_ref().map_or_else(String::new, |referee| referee.progress(&world)))?;
+        if referee.as_ref().is_some_and(|referee| referee.outcome().is_some()) {
+            break;
+        }
     }
+    recorder.finish()?;
 
-    Ok(())
+    println!(
+        "tick {}: food stored {}, ants {}, killed {}, predators slain {}",
+        world.environment.tick(),
+        world.stats.food_stored,
+        world.ants().len(),
+        world.stats.ants_killed,
+        world.stats.predators_killed
+    );
+    let Some(mut referee) = referee else {
+        return Ok(true);
+    };
+    referee.finish(&world);
+    for line in referee.report() {
+        println!("{}", line);
+    }
+    Ok(referee.outcome() == Some(true))
+}
+
+
+// Runs without the TUI, serving the world to TCP clients until stopped, out of
+// ticks or the scenario is decided. Returns false if the scenario failed.
+fn run_server(config: &config::Config, params: &params::Params, brains: brain::Brains, scenario: Option<&Scenario>, address: &str) -> Result<bool> {
+    let (mut world, mut rng) = create_world(config, params, brains, scenario)?;
+    let mut recorder = recorder::Recorder::create(config, &world)?;
+    let mut referee = scenario.map(Referee::new);
+    let mut server = server::Server::bind(address, &world)?;
+    println!("Serving on {}", server.local_address()?);
+
+    while config.ticks.is_none_or(|ticks| world.environment.tick() < ticks) {
+        server.poll(&mut world);
+        if server.should_step() {
+            world.step(&mut rng);
+            if let Some(referee) = referee.as_mut() {
+                referee.update(&mut world, &mut rng);
+            }
+            recorder.record(&world)?;
+            server.broadcast_tick(&world);
+            if referee.as_ref().is_some_and(|referee| referee.outcome().is_some()) {
+                break;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    recorder.finish()?;
+    let Some(mut referee) = referee else {
+        return Ok(true);
+    };
+    referee.finish(&world);
+    for line in referee.report() {
+        println!("{}", line);
+    }
+    Ok(referee.outcome() == Some(true))
+}
+
+
+// Prints a table of timings, a row per benchmark world as it finishes
+fn run_bench(config: &bench::BenchConfig) {
+    println!(
+        "Benchmark: {} ticks per world, seed {}, {} thread(s), {} ants{}",
+        config.ticks,
+        config.seed,
+        config.threads,
+        if config.compact { "compact" } else { "extended" },
+        if cfg!(debug_assertions) { " (debug build, use --release for real numbers)" } else { "" }
+    );
+    println!("{:>11} {:>8} {:>12} {:>12} {:>10}", "world", "ants", "total ms", "ticks/sec", "ns/ant");
+
+    for result in bench::run(config) {
+        println!(
+            "{:>11} {:>8} {:>12.1} {:>12.1} {:>10.1}",
+            format!("{}x{}", result.width, result.height),
+            result.ants,
+            result.elapsed.as_secs_f64() * 1000.0,
+            result.ticks_per_sec(),
+            result.ns_per_ant()
+        );
+    }
+}
+
+
+type Loaded = (config::Config, params::Params, brain::Brains, Option<Scenario>);
+
+fn load_config() -> std::result::Result<Loaded, String> {
+    let mut config = config::Config::from_args(std::env::args().skip(1))?;
+    let scenario = config.load_scenario()?;
+    let mut params = config.params()?;
+    if let Some(scenario) = &scenario {
+        params = scenario.params(&params);
+    }
+    let brains = config.build_brains(&params)?;
+    Ok((config, params, brains, scenario))
 }
 
 
 fn main() {
-    if let Err(e) = display::Display::new().run_program(run_simulation) {
-        eprintln!("Error: {}", e);
+    let (config, params, brains, scenario) = match load_config() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    match &config.command {
+        config::Command::Evolve(evolve_config) => {
+            let result = evolve::run(evolve_config, params, |scores| {
+                println!(
+                    "Generation {}/{}: best {} mean {:.1} (food over {} runs of {} ticks)",
+                    scores.generation, evolve_config.generations, scores.best, scores.mean, evolve::EVALUATION_RUNS, evolve_config.ticks
+                );
+            });
+            match result {
+                Ok((champion, score)) => println!("Champion (score {}) written to {}:\n{}", score, evolve_config.out.display(), champion),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        config::Command::Bench(bench_config) => {
+            run_bench(bench_config);
+        }
+        config::Command::Run if let Some(address) = &config.serve => {
+            match run_server(&config, &params, brains, scenario.as_ref(), address) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(3),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        config::Command::Run if config.renderer != config::RendererKind::Tui => {
+            let mut renderer: Box<dyn Renderer> = match config.renderer {
+                config::RendererKind::Text => Box::new(TextRenderer::new(std::io::stdout().lock())),
+                _ => Box::new(NullRenderer),
+            };
+            match run_headless(&config, &params, brains, scenario.as_ref(), renderer.as_mut()) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(3),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        #[cfg(feature = "tui")]
+        config::Command::Run => {
+            if let Err(e) = display::Display::new().run_program(|display| run_simulation(display, &config, &params, brains, scenario.as_ref())) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        #[cfg(not(feature = "tui"))]
+        config::Command::Run => {
+            eprintln!("Error: built without the terminal view (the \"tui\" feature); use --renderer text, --headless or --serve");
+            std::process::exit(2);
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,180 @@
+use crate::ant::{AntRole, Carrying};
+use crate::world::{Objects, World};
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+
+/// One row of the time series
+pub struct Metrics {
+    /// Tick sampled
+    pub tick: u64,
+    /// Live ants of each role, indexed by `AntRole`
+    pub ants_by_role: [usize; AntRole::COUNT as usize],
+    /// Ants on their way home with food
+    pub carrying_food: usize,
+    /// Food delivered to the nest so far
+    pub food_stored: u32,
+    /// Food lying on the grid
+    pub food_remaining: usize,
+    /// Food trail strength averaged over every tile
+    pub mean_food_pheromone: f64,
+    /// Home trail strength averaged over every tile
+    pub mean_home_pheromone: f64,
+}
+
+impl Metrics {
+    /// Counts up the world as it is now
+    pub fn sample(world: &World) -> Self {
+        let mut ants_by_role = [0; AntRole::COUNT as usize];
+        let mut carrying_food = 0;
+        for (_, ant, _) in world.ants().iter() {
+            ants_by_role[ant.ant_role() as usize] += 1;
+            if ant.carrying() == Carrying::Food {
+                carrying_food += 1;
+            }
+        }
+
+        let (mut food_remaining, mut food_pheromone, mut home_pheromone) = (0, 0u64, 0u64);
+        for tile in world.grid() {
+            if tile.is(Objects::Food) {
+                food_remaining += 1;
+            }
+            food_pheromone += tile.pheromone() as u64;
+            home_pheromone += tile.home_pheromone() as u64;
+        }
+        let tiles = world.grid().len().max(1) as f64;
+
+        Metrics {
+            tick: world.environment.tick(),
+            ants_by_role,
+            carrying_food,
+            food_stored: world.stats.food_stored,
+            food_remaining,
+            mean_food_pheromone: food_pheromone as f64 / tiles,
+            mean_home_pheromone: home_pheromone as f64 / tiles,
+        }
+    }
+
+    // (column, value) pairs in output order
+    fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![("tick".to_string(), self.tick.to_string())];
+        for role in AntRole::ALL {
+            fields.push((role.name().to_ascii_lowercase() + "s", self.ants_by_role[role as usize].to_string()));
+        }
+        fields.push(("carrying_food".to_string(), self.carrying_food.to_string()));
+        fields.push(("food_stored".to_string(), self.food_stored.to_string()));
+        fields.push(("food_remaining".to_string(), self.food_remaining.to_string()));
+        fields.push(("mean_food_pheromone".to_string(), format!("{:.4}", self.mean_food_pheromone)));
+        fields.push(("mean_home_pheromone".to_string(), format!("{:.4}", self.mean_home_pheromone)));
+        fields
+    }
+}
+
+// *****************************************************
+
+/// How a metrics file is laid out
+pub enum Format {
+    /// A header row, then comma-separated values
+    Csv,
+    /// A JSON object per row
+    JsonLines,
+}
+
+/// Appends a row of metrics every `every` ticks, as CSV or JSON lines depending
+/// on the file extension (.csv, or .jsonl / .json)
+pub struct MetricsWriter {
+    out: BufWriter<File>,
+    format: Format,
+    every: u64,
+    wrote_header: bool,
+}
+
+impl MetricsWriter {
+    /// Starts a fresh series at `path` sampled every `every` ticks (at least 1),
+    /// in the format its extension names; any other extension is an error
+    pub fn create(path: &Path, every: u64) -> Result<Self, String> {
+        let format = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Format::Csv,
+            Some("jsonl" | "json") => Format::JsonLines,
+            _ => return Err(format!("{}: metrics file must end in .csv, .jsonl or .json", path.display())),
+        };
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(MetricsWriter { out: BufWriter::new(file), format, every: every.max(1), wrote_header: false })
+    }
+
+    /// Writes a row if this tick is one of every `every`; call after every step
+    pub fn record(&mut self, world: &World) -> io::Result<()> {
+        if !world.environment.tick().is_multiple_of(self.every) {
+            return Ok(());
+        }
+        let fields = Metrics::sample(world).fields();
+        match self.format {
+            Format::Csv => {
+                if !self.wrote_header {
+                    let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+                    writeln!(self.out, "{}", names.join(","))?;
+                    self.wrote_header = true;
+                }
+                let values: Vec<&str> = fields.iter().map(|(_, value)| value.as_str()).collect();
+                writeln!(self.out, "{}", values.join(","))
+            }
+            Format::JsonLines => {
+                let pairs: Vec<String> = fields.iter().map(|(name, value)| format!("\"{}\":{}", name, value)).collect();
+                writeln!(self.out, "{{{}}}", pairs.join(","))
+            }
+        }
+    }
+
+    /// Pushes buffered rows to the file, leaving a complete CSV or JSON lines
+    /// series for notebooks to read
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ant::Ant;
+    use crate::params::Params;
+    use crate::world::WorldSize;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn world() -> World {
+        let mut world = World::from_map(&mut StdRng::seed_from_u64(1), &Params::default(), &WorldSize::default(), &["X.O.......", "##########"]).unwrap();
+        world.add_ant(9, 0, Ant::with_role(AntRole::Soldier)).unwrap();
+        world
+    }
+
+    #[test]
+    fn counts_ants_by_role_and_food() {
+        let metrics = Metrics::sample(&world());
+        assert_eq!(metrics.tick, 0);
+        assert_eq!(metrics.ants_by_role, [1, 1, 0, 0]);
+        assert_eq!((metrics.carrying_food, metrics.food_stored, metrics.food_remaining), (0, 0, 1));
+    }
+
+    #[test]
+    fn writes_a_csv_header_then_a_row_per_interval() {
+        let path = std::env::temp_dir().join(format!("ant_sim_metrics_{}.csv", std::process::id()));
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut writer = MetricsWriter::create(&path, 2).unwrap();
+        for _ in 0..4 {
+            writer.record(&world).unwrap();
+            world.step(&mut rng);
+        }
+        writer.flush().unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[0], "tick,workers,soldiers,scouts,queens,carrying_food,food_stored,food_remaining,mean_food_pheromone,mean_home_pheromone");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("0,1,1,0,0,"), "{}", lines[1]);
+        assert!(lines[2].starts_with("2,"), "{}", lines[2]);
+        assert!(MetricsWriter::create(&path.with_extension("txt"), 1).is_err());
+    }
+}
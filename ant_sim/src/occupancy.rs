@@ -0,0 +1,126 @@
+use crate::ant_store::AntId;
+use crate::constants;
+
+
+const NONE: AntId = AntId::MAX;
+
+/// Spatial index of ants, kept separate from the tile objects so an ant standing
+/// on food (or anything else) doesn't erase it. Each cell holds a short linked
+/// list of ant ids threaded through `next`.
+pub struct Occupancy {
+    width: usize,
+    height: usize,
+    heads: Vec<AntId>, // First ant in each cell
+    counts: Vec<u8>,
+    next: Vec<AntId>,  // Next ant in the same cell, indexed by ant id
+}
+
+impl Occupancy {
+    /// No ants on a `width` x `height` grid
+    pub fn new(width: usize, height: usize) -> Self {
+        Occupancy {
+            width,
+            height,
+            heads: vec![NONE; width * height],
+            counts: vec![0; width * height],
+            next: Vec::new(),
+        }
+    }
+
+    fn idx(&self, x: usize, y: usize) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    // Getters
+    /// Ids of the ants on (x, y), most recent arrival first
+    pub fn at(&self, x: usize, y: usize) -> CellAnts<'_> {
+        let current = self.idx(x, y).map_or(NONE, |index| self.heads[index]);
+        CellAnts { occupancy: self, current }
+    }
+
+    /// Number of ants on (x, y); 0 off the grid
+    pub fn count(&self, x: usize, y: usize) -> usize {
+        self.idx(x, y).map_or(0, |index| self.counts[index] as usize)
+    }
+
+    /// Whether (x, y) already holds `MAX_ANTS_PER_TILE` ants
+    pub fn is_full(&self, x: usize, y: usize) -> bool {
+        self.count(x, y) >= constants::MAX_ANTS_PER_TILE
+    }
+
+    // Setters
+    /// Returns false if the cell is off the grid or already holds MAX_ANTS_PER_TILE ants
+    pub fn insert(&mut self, x: usize, y: usize, ant: AntId) -> bool {
+        if self.is_full(x, y) {
+            return false;
+        }
+        let Some(index) = self.idx(x, y) else {
+            return false;
+        };
+        if self.next.len() <= ant as usize {
+            self.next.resize(ant as usize + 1, NONE);
+        }
+        self.next[ant as usize] = self.heads[index];
+        self.heads[index] = ant;
+        self.counts[index] += 1;
+        true
+    }
+
+    /// Takes `ant` off (x, y); does nothing if it isn't there
+    pub fn remove(&mut self, x: usize, y: usize, ant: AntId) {
+        let Some(index) = self.idx(x, y) else {
+            return;
+        };
+        if self.heads[index] == ant {
+            self.heads[index] = self.next[ant as usize];
+        } else {
+            let mut current = self.heads[index];
+            while current != NONE && self.next[current as usize] != ant {
+                current = self.next[current as usize];
+            }
+            if current == NONE {
+                return;
+            }
+            self.next[current as usize] = self.next[ant as usize];
+        }
+        self.next[ant as usize] = NONE;
+        self.counts[index] -= 1;
+    }
+
+    /// Moves an ant between cells, leaving it in place if the destination is full
+    pub fn relocate(&mut self, from: (usize, usize), to: (usize, usize), ant: AntId) -> bool {
+        if from == to {
+            return true;
+        }
+        if self.is_full(to.0, to.1) || self.idx(to.0, to.1).is_none() {
+            return false;
+        }
+        self.remove(from.0, from.1, ant);
+        self.insert(to.0, to.1, ant)
+    }
+}
+
+// *****************************************************
+
+/// Iterator over the ants on one cell, from `Occupancy::at`
+pub struct CellAnts<'a> {
+    occupancy: &'a Occupancy,
+    current: AntId,
+}
+
+impl Iterator for CellAnts<'_> {
+    type Item = AntId;
+
+    fn next(&mut self) -> Option<AntId> {
+        if self.current == NONE {
+            return None;
+        }
+        let ant = self.current;
+        self.current = self.occupancy.next[ant as usize];
+        Some(ant)
+    }
+}
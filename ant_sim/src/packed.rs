@@ -0,0 +1,152 @@
+/// Failed conversion into one of the packed enums
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnumError {
+    /// A number past the last variant
+    OutOfRange {
+        /// The enum's name
+        kind: &'static str,
+        /// The number given
+        value: u8,
+    },
+    /// A name that isn't one of the variants
+    UnknownName {
+        /// The enum's name
+        kind: &'static str,
+        /// The name given
+        name: String,
+        /// The variant names
+        expected: &'static [&'static str],
+    },
+}
+
+impl std::fmt::Display for EnumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EnumError::OutOfRange { kind, value } => write!(f, "{} is not a valid {}", value, kind),
+            EnumError::UnknownName { kind, name, expected } => {
+                write!(f, "unknown {} '{}' (expected one of {})", kind, name, expected.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnumError {}
+
+// *****************************************************
+
+// Declares a fieldless enum stored as a few bits of a packed value. Variants
+// are numbered from 0 in order and get:
+//   COUNT, ALL, NAMES, name() and from_bits() (wrapping, for masked fields)
+//   TryFrom<u8> / From<Enum> for u8
+//   Display / FromStr using the variant name (parsing ignores case)
+//   Debug, Clone, Copy, PartialEq, Eq and Hash
+macro_rules! packed_enum {
+    ($(#[$meta:meta])* $vis:vis enum $name:ident { $($(#[$variant_meta:meta])* $variant:ident),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[repr(u8)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant),+
+        }
+
+        impl $name {
+            /// Number of variants
+            pub const COUNT: u8 = [$(stringify!($variant)),+].len() as u8;
+            /// Every variant, in order
+            pub const ALL: [Self; Self::COUNT as usize] = [$(Self::$variant),+];
+            /// Variant names, in order
+            pub const NAMES: [&'static str; Self::COUNT as usize] = [$(stringify!($variant)),+];
+
+            /// The variant's name, as written in the source
+            pub fn name(self) -> &'static str {
+                Self::NAMES[self as usize]
+            }
+
+            /// Wraps out-of-range values; only for bits already limited by a mask
+            pub fn from_bits(value: u8) -> Self {
+                Self::ALL[(value % Self::COUNT) as usize]
+            }
+        }
+
+        impl TryFrom<u8> for $name {
+            type Error = $crate::packed::EnumError;
+
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                Self::ALL
+                    .get(value as usize)
+                    .copied()
+                    .ok_or($crate::packed::EnumError::OutOfRange { kind: stringify!($name), value })
+            }
+        }
+
+        impl From<$name> for u8 {
+            fn from(value: $name) -> Self {
+                value as u8
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.pad(self.name())
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = $crate::packed::EnumError;
+
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                Self::ALL.into_iter().find(|value| value.name().eq_ignore_ascii_case(name)).ok_or_else(|| {
+                    $crate::packed::EnumError::UnknownName { kind: stringify!($name), name: name.to_string(), expected: &Self::NAMES }
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use packed_enum;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    packed_enum! {
+        enum Colour {
+            Red,
+            Green,
+            Blue,
+        }
+    }
+
+    #[test]
+    fn all_and_names_follow_the_bit_values() {
+        assert_eq!(Colour::COUNT, 3);
+        for (bits, colour) in Colour::ALL.into_iter().enumerate() {
+            assert_eq!(u8::from(colour), bits as u8);
+            assert_eq!(Colour::try_from(bits as u8), Ok(colour));
+            assert_eq!(colour.name(), Colour::NAMES[bits]);
+        }
+        assert_eq!(Colour::NAMES, ["Red", "Green", "Blue"]);
+        assert_eq!(Colour::from_bits(4), Colour::Green);
+    }
+
+    #[test]
+    fn out_of_range_bits_are_an_error() {
+        assert_eq!(Colour::try_from(3), Err(EnumError::OutOfRange { kind: "Colour", value: 3 }));
+    }
+
+    #[test]
+    fn names_round_trip_ignoring_case() {
+        for colour in Colour::ALL {
+            assert_eq!(colour.to_string().parse::<Colour>(), Ok(colour));
+            assert_eq!(colour.to_string().to_uppercase().parse::<Colour>(), Ok(colour));
+        }
+        assert_eq!("bLuE".parse::<Colour>(), Ok(Colour::Blue));
+    }
+
+    #[test]
+    fn unknown_names_are_an_error() {
+        let error = "Purple".parse::<Colour>().unwrap_err();
+        assert_eq!(error, EnumError::UnknownName { kind: "Colour", name: "Purple".to_string(), expected: &["Red", "Green", "Blue"] });
+        assert_eq!(error.to_string(), "unknown Colour 'Purple' (expected one of Red, Green, Blue)");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::constants;
+
+use std::path::Path;
+
+
+/// Tunable behaviour parameters for the built-in brain and colony make-up.
+/// Every field is one gene for `evolve`, saved as `name = value` lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Params {
+    /// % chance per tick a wandering ant turns
+    pub turn_chance: u8,
+    /// % chance a worker digs into ground it faces
+    pub dig_chance: u8,
+    /// % chance per tick soil is dumped above ground
+    pub soil_drop_chance: u8,
+    /// Food pheromone ahead an outbound ant follows (4 = off, trails top out at 3)
+    pub food_trail_threshold: u8,
+    /// Home pheromone a food carrier needs to climb the gradient
+    pub home_trail_threshold: u8,
+    /// Share of new ants that are soldiers
+    pub soldier_percentage: u8,
+}
+
+impl Params {
+    /// Number of fields, each one gene
+    pub const GENE_COUNT: usize = 6;
+
+    /// Name and inclusive range of each gene, in `genes()` order
+    pub const GENES: [(&'static str, u8, u8); Self::GENE_COUNT] = [
+        ("turn_chance", 0, 100),
+        ("dig_chance", 0, 100),
+        ("soil_drop_chance", 0, 100),
+        ("food_trail_threshold", 1, 4),
+        ("home_trail_threshold", 1, 7),
+        ("soldier_percentage", 0, 100),
+    ];
+
+    /// The fields as a genome, in `GENES` order
+    pub fn genes(&self) -> [u8; Self::GENE_COUNT] {
+        [
+            self.turn_chance,
+            self.dig_chance,
+            self.soil_drop_chance,
+            self.food_trail_threshold,
+            self.home_trail_threshold,
+            self.soldier_percentage,
+        ]
+    }
+
+    /// Out-of-range genes are clamped
+    pub fn from_genes(genes: [u8; Self::GENE_COUNT]) -> Self {
+        let gene = |i: usize| genes[i].clamp(Self::GENES[i].1, Self::GENES[i].2);
+        Params {
+            turn_chance: gene(0),
+            dig_chance: gene(1),
+            soil_drop_chance: gene(2),
+            food_trail_threshold: gene(3),
+            home_trail_threshold: gene(4),
+            soldier_percentage: gene(5),
+        }
+    }
+
+    /// A copy with the gene called `name` set to `value` (clamped), or None if
+    /// there is no such gene
+    pub fn with_gene(&self, name: &str, value: u8) -> Option<Self> {
+        let index = Self::GENES.iter().position(|(gene, _, _)| *gene == name)?;
+        let mut genes = self.genes();
+        genes[index] = value;
+        Some(Self::from_genes(genes))
+    }
+
+    /// Reads a genome file of `name = value` lines; genes it leaves out keep
+    /// their defaults
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut genes = Params::default().genes();
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("{}: line {}: {}", path.display(), number + 1, message);
+            let (name, value) = line.split_once('=').ok_or_else(|| error("expected 'name = value'".to_string()))?;
+            let (name, value) = (name.trim(), value.trim());
+            let index = Self::GENES
+                .iter()
+                .position(|(gene, _, _)| *gene == name)
+                .ok_or_else(|| error(format!("unknown parameter '{}'", name)))?;
+            genes[index] = value.parse().map_err(|_| error(format!("'{}' is not a number from 0 to 255", value)))?;
+        }
+        Ok(Self::from_genes(genes))
+    }
+
+    /// Every gene on one line, e.g. "turn_chance 20, dig_chance 10, ..."
+    pub fn summary(&self) -> String {
+        let genes: Vec<String> = Self::GENES.iter().zip(self.genes()).map(|((name, _, _), value)| format!("{} {}", name, value)).collect();
+        genes.join(", ")
+    }
+
+    /// Writes every gene as a `name = value` line, readable by `load`
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            turn_chance: constants::ANT_TURN_CHANCE_PERCENTAGE,
+            dig_chance: constants::DIG_CHANCE_PERCENTAGE,
+            soil_drop_chance: constants::SOIL_DROP_CHANCE_PERCENTAGE,
+            food_trail_threshold: constants::FOOD_TRAIL_THRESHOLD,
+            home_trail_threshold: 1,
+            soldier_percentage: constants::SOLDIER_PERCENTAGE,
+        }
+    }
+}
+
+impl std::fmt::Display for Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for ((name, _, _), value) in Self::GENES.iter().zip(self.genes()) {
+            writeln!(f, "{} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
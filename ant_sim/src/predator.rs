@@ -0,0 +1,47 @@
+/// The kinds of predator hunting the colony
+pub enum PredatorKind {
+    /// Roams the surface and tunnels
+    Spider,
+    /// Lies in wait at the bottom of its pit
+    Antlion,
+}
+
+impl From<PredatorKind> for String {
+    fn from(kind: PredatorKind) -> Self {
+        match kind {
+            PredatorKind::Spider => "Spider".to_string(),
+            PredatorKind::Antlion => "Antlion".to_string(),
+        }
+    }
+}
+
+// *****************************************************
+
+/// A predator on the grid, where it also shows as an `Objects::Predator` tile
+pub struct Predator {
+    /// Spider or antlion
+    pub kind: PredatorKind,
+    /// Column
+    pub x: usize,
+    /// Row
+    pub y: usize,
+    /// Health left; dead at 0
+    pub health: u8,
+}
+
+impl Predator {
+    /// A predator at (x, y); `World::add_predator` also marks its tile
+    pub fn new(kind: PredatorKind, x: usize, y: usize, health: u8) -> Self {
+        Predator { kind, x, y, health }
+    }
+
+    /// Whether it has health left
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    /// Whether it moves around rather than waiting in one place
+    pub fn roams(&self) -> bool {
+        matches!(self.kind, PredatorKind::Spider)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::config::Config;
+use ant_sim::events::EventLog;
+use ant_sim::image::{Frame, GifWriter};
+use ant_sim::metrics::MetricsWriter;
+use ant_sim::world::World;
+
+use std::io;
+use std::path::PathBuf;
+
+
+// Hundredths of a second each GIF frame is shown for
+const GIF_FRAME_DELAY: u16 = 5;
+
+// Everything a run writes out as it goes: metrics, events and pictures
+pub struct Recorder {
+    metrics: Option<MetricsWriter>,
+    event_log: Option<EventLog>,
+    frames: Option<PathBuf>,
+    gif: Option<GifWriter>,
+    frames_every: u64,
+    scale: usize,
+}
+
+impl Recorder {
+    // Opens the outputs asked for in `config`, and records the starting frame
+    pub fn create(config: &Config, world: &World) -> io::Result<Self> {
+        let metrics = config.metrics.as_ref().map(|path| MetricsWriter::create(path, config.metrics_every)).transpose();
+        let event_log = config.events.as_deref().map(EventLog::create).transpose();
+        if let Some(directory) = &config.frames {
+            std::fs::create_dir_all(directory)?;
+        }
+        let gif = match &config.gif {
+            Some(path) => Some(
+                GifWriter::create(path, world.width * config.scale, world.height * config.scale, GIF_FRAME_DELAY)
+                    .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?,
+            ),
+            None => None,
+        };
+
+        let mut recorder = Recorder {
+            metrics: metrics.map_err(io::Error::other)?,
+            event_log: event_log.map_err(io::Error::other)?,
+            frames: config.frames.clone(),
+            gif,
+            frames_every: config.frames_every.max(1),
+            scale: config.scale,
+        };
+        recorder.record_frame(world)?;
+        Ok(recorder)
+    }
+
+    // Called after every step
+    pub fn record(&mut self, world: &World) -> io::Result<()> {
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics.record(world)?;
+        }
+        if let Some(log) = self.event_log.as_mut() {
+            log.write(world.events())?;
+        }
+        if world.environment.tick().is_multiple_of(self.frames_every) {
+            self.record_frame(world)?;
+        }
+        Ok(())
+    }
+
+    fn record_frame(&mut self, world: &World) -> io::Result<()> {
+        if self.frames.is_none() && self.gif.is_none() {
+            return Ok(());
+        }
+        let frame = Frame::render(world, self.scale)?;
+        if let Some(directory) = &self.frames {
+            frame.write_ppm(&directory.join(format!("frame-{:06}.ppm", world.environment.tick())))?;
+        }
+        if let Some(gif) = self.gif.as_mut() {
+            gif.add_frame(&frame)?;
+        }
+        Ok(())
+    }
+
+    // Saves the world as it is now, into the frames directory if there is one
+    #[cfg(feature = "tui")]
+    pub fn snapshot(&self, world: &World) -> io::Result<PathBuf> {
+        let name = format!("snapshot-{:06}.ppm", world.environment.tick());
+        let path = self.frames.as_ref().map_or_else(|| PathBuf::from(&name), |directory| directory.join(&name));
+        Frame::render(world, self.scale)?.write_ppm(&path)?;
+        Ok(path)
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        if let Some(mut metrics) = self.metrics {
+            metrics.flush()?;
+        }
+        if let Some(mut log) = self.event_log {
+            log.flush()?;
+        }
+        if let Some(gif) = self.gif {
+            gif.finish()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::world::World;
+
+use std::io::{self, Write};
+
+
+/// Draws the world once per tick, with `message` (which may run to several
+/// lines) above it
+pub trait Renderer {
+    /// Draws one frame
+    fn draw(&mut self, world: &World, message: &str) -> io::Result<()>;
+}
+
+/// One line summing up the world, shown above the grid
+pub fn status_line(world: &World) -> String {
+    let time_of_day = if world.environment.is_night() { "Night" } else { "Day" };
+    let weather = if world.environment.is_raining() { "Rain" } else { "Clear" };
+    format!(
+        "Tick {} | {} | {} | Food stored: {} | Ants: {} | Ants killed: {} | Predators slain: {}",
+        world.environment.tick(), time_of_day, weather, world.stats.food_stored,
+        world.ants().len(), world.stats.ants_killed, world.stats.predators_killed
+    )
+}
+
+// *****************************************************
+
+/// Writes each frame as plain lines of glyphs followed by a blank line, for logs
+/// and for comparing runs as text
+pub struct TextRenderer<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TextRenderer<W> {
+    /// Writes frames to `out`
+    pub fn new(out: W) -> Self {
+        TextRenderer { out }
+    }
+
+    /// Gives back the writer, e.g. to inspect what was drawn
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Renderer for TextRenderer<W> {
+    fn draw(&mut self, world: &World, message: &str) -> io::Result<()> {
+        if !message.is_empty() {
+            writeln!(self.out, "{}", message)?;
+        }
+        writeln!(self.out, "{}", status_line(world))?;
+        let mut row = String::with_capacity(world.width);
+        for y in 0..world.height {
+            row.clear();
+            row.extend((0..world.width).map(|x| world.glyph(x, y)));
+            writeln!(self.out, "{}", row)?;
+        }
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+}
+
+// *****************************************************
+
+/// Draws nothing, so a run costs only the simulation
+pub struct NullRenderer;
+
+impl Renderer for NullRenderer {
+    fn draw(&mut self, _world: &World, _message: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Params;
+    use crate::world::WorldSize;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    #[test]
+    fn text_renderer_draws_the_status_and_the_grid() {
+        let rows = ["..O..X..", "......@.", "###::###", "########"];
+        let world = World::from_map(&mut StdRng::seed_from_u64(1), &Params::default(), &WorldSize::default(), &rows).unwrap();
+        let mut renderer = TextRenderer::new(Vec::new());
+        renderer.draw(&world, "Seed 1").unwrap();
+        let text = String::from_utf8(renderer.into_inner()).unwrap();
+        let status = "Tick 0 | Day | Clear | Food stored: 0 | Ants: 1 | Ants killed: 0 | Predators slain: 0";
+        assert_eq!(text, format!("Seed 1\n{}\n{}\n\n", status, rows.join("\n")));
+    }
+}
@@ -0,0 +1,521 @@
+//! A small rule language for ant behaviour, one rule per line:
+//!
+//! ```text
+//!   # comments start with '#'
+//!   when carrying Food and home_pheromone ahead > 2 then move forward
+//!   when here is Food and not raining then pick up
+//!   when chance 20 then turn left
+//!   otherwise wander
+//! ```
+//!
+//! Rules are checked top to bottom and the first one whose conditions all hold
+//! decides the action. If nothing matches the ant rests.
+//!
+//! Conditions (any can be prefixed with `not`):
+//! ```text
+//!   carrying NAME, role NAME                    the ant's own state
+//!   health/max_health/strength/energy/age OP N  the ant's stats (in compact
+//!                                               worlds ants never tire or age)
+//!   food_pheromone/home_pheromone/ants [PLACE] OP N
+//!   PLACE is OBJECT                             e.g. `ahead is Obstacle`
+//!   raining, night, at_nest, underground, blocked, predator_near, predator_adjacent,
+//!   food_near                                   food within smelling range
+//!   chance N                                    true N% of the time
+//! ```
+//! where PLACE is here, ahead, left or right (default here) and OP is one of
+//! < <= > >= == !=.
+//!
+//! Actions:
+//! ```text
+//!   move forward, move home, move home_vector, move to_predator, move to_food,
+//!   move along_trail (strongest food trail in the three cells ahead), turn left/right/around/random,
+//!   pick up, drop, dig, attack, rest, wander
+//! ```
+
+use crate::ant::{self, AntRole, Carrying};
+use crate::brain::{self, Action, AntBrain, Surroundings};
+use crate::constants;
+use crate::world::Objects;
+
+use rand::{Rng, RngCore};
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+
+/// Why a rule file didn't parse
+#[derive(Debug)]
+pub struct ParseError {
+    /// Line of the bad rule, counting from 1
+    pub line: usize,
+    /// What was wrong with it
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// *****************************************************
+
+enum Place {
+    Here,
+    Ahead,
+    Left,
+    Right,
+}
+
+impl Place {
+    fn offset(&self, ant: &ant::Ant) -> (isize, isize) {
+        match self {
+            Place::Here => (0, 0),
+            Place::Ahead => ant.direction().delta(),
+            Place::Left => ant.direction().turn_left().delta(),
+            Place::Right => ant.direction().turn_right().delta(),
+        }
+    }
+}
+
+enum Quantity {
+    Health,
+    MaxHealth,
+    Strength,
+    Energy,
+    Age,
+    FoodPheromone(Place),
+    HomePheromone(Place),
+    Ants(Place),
+}
+
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn holds(&self, left: usize, right: usize) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+enum Condition {
+    Not(Box<Condition>),
+    Carrying(Carrying),
+    Role(AntRole),
+    Is(Place, Objects),
+    Compare(Quantity, Comparison, usize),
+    Raining,
+    Night,
+    AtNest,
+    Underground,
+    Blocked,
+    PredatorNear,
+    PredatorAdjacent,
+    FoodNear,
+    Chance(u8),
+}
+
+enum RuleAction {
+    MoveForward,
+    MoveHome,
+    MoveHomeVector,
+    MoveToPredator,
+    MoveToFood,
+    MoveAlongTrail,
+    TurnLeft,
+    TurnRight,
+    TurnAround,
+    TurnRandom,
+    PickUp,
+    Drop,
+    Dig,
+    Attack,
+    Rest,
+    Wander,
+}
+
+struct Rule {
+    conditions: Vec<Condition>,
+    action: RuleAction,
+}
+
+// *****************************************************
+
+struct Parser<'a> {
+    line: usize,
+    words: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: String) -> ParseError {
+        ParseError { line: self.line, message }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.words.get(self.position).copied()
+    }
+
+    fn next(&mut self, expected: &str) -> Result<&'a str, ParseError> {
+        let word = self.peek().ok_or_else(|| self.error(format!("expected {} at end of line", expected)))?;
+        self.position += 1;
+        Ok(word)
+    }
+
+    fn expect(&mut self, keyword: &str) -> Result<(), ParseError> {
+        match self.next(&format!("'{}'", keyword))? {
+            word if word == keyword => Ok(()),
+            word => Err(self.error(format!("expected '{}', found '{}'", keyword, word))),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, ParseError> {
+        let word = self.next("a number")?;
+        word.parse().map_err(|_| self.error(format!("expected a number, found '{}'", word)))
+    }
+
+    fn place(&mut self) -> Option<Place> {
+        let place = match self.peek()? {
+            "here" => Place::Here,
+            "ahead" => Place::Ahead,
+            "left" => Place::Left,
+            "right" => Place::Right,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(place)
+    }
+
+    fn comparison(&mut self) -> Result<Comparison, ParseError> {
+        Ok(match self.next("a comparison")? {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterEqual,
+            "==" | "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            word => return Err(self.error(format!("expected a comparison (< <= > >= == !=), found '{}'", word))),
+        })
+    }
+
+    fn named<T: std::str::FromStr>(&mut self, what: &str) -> Result<T, ParseError> {
+        let word = self.next(what)?;
+        word.parse().map_err(|_| self.error(format!("unknown {} '{}'", what, word)))
+    }
+
+    fn compare(&mut self, quantity: Quantity) -> Result<Condition, ParseError> {
+        let comparison = self.comparison()?;
+        Ok(Condition::Compare(quantity, comparison, self.number()?))
+    }
+
+    fn condition(&mut self) -> Result<Condition, ParseError> {
+        Ok(match self.next("a condition")? {
+            "not" => Condition::Not(Box::new(self.condition()?)),
+            "carrying" => Condition::Carrying(self.named("carried item")?),
+            "role" => Condition::Role(self.named("role")?),
+            "health" => self.compare(Quantity::Health)?,
+            "max_health" => self.compare(Quantity::MaxHealth)?,
+            "strength" => self.compare(Quantity::Strength)?,
+            "energy" => self.compare(Quantity::Energy)?,
+            "age" => self.compare(Quantity::Age)?,
+            "food_pheromone" => {
+                let place = self.place().unwrap_or(Place::Here);
+                self.compare(Quantity::FoodPheromone(place))?
+            }
+            "home_pheromone" => {
+                let place = self.place().unwrap_or(Place::Here);
+                self.compare(Quantity::HomePheromone(place))?
+            }
+            "ants" => {
+                let place = self.place().unwrap_or(Place::Here);
+                self.compare(Quantity::Ants(place))?
+            }
+            "raining" => Condition::Raining,
+            "night" => Condition::Night,
+            "at_nest" => Condition::AtNest,
+            "underground" => Condition::Underground,
+            "blocked" => Condition::Blocked,
+            "predator_near" => Condition::PredatorNear,
+            "predator_adjacent" => Condition::PredatorAdjacent,
+            "food_near" => Condition::FoodNear,
+            "chance" => Condition::Chance(self.number()?.min(100) as u8),
+            word => {
+                self.position -= 1;
+                match self.place() {
+                    Some(place) => {
+                        self.expect("is")?;
+                        Condition::Is(place, self.named("object")?)
+                    }
+                    None => return Err(self.error(format!("unknown condition '{}'", word))),
+                }
+            }
+        })
+    }
+
+    fn action(&mut self) -> Result<RuleAction, ParseError> {
+        let action = match self.next("an action")? {
+            "move" => match self.next("'forward', 'home', 'home_vector', 'to_predator', 'to_food' or 'along_trail'")? {
+                "forward" => RuleAction::MoveForward,
+                "home" => RuleAction::MoveHome,
+                "home_vector" => RuleAction::MoveHomeVector,
+                "to_predator" => RuleAction::MoveToPredator,
+                "to_food" => RuleAction::MoveToFood,
+                "along_trail" => RuleAction::MoveAlongTrail,
+                word => return Err(self.error(format!("can't move '{}'", word))),
+            },
+            "turn" => match self.next("'left', 'right', 'around' or 'random'")? {
+                "left" => RuleAction::TurnLeft,
+                "right" => RuleAction::TurnRight,
+                "around" => RuleAction::TurnAround,
+                "random" => RuleAction::TurnRandom,
+                word => return Err(self.error(format!("can't turn '{}'", word))),
+            },
+            "pick" => {
+                self.expect("up")?;
+                RuleAction::PickUp
+            }
+            "drop" => RuleAction::Drop,
+            "dig" => RuleAction::Dig,
+            "attack" => RuleAction::Attack,
+            "rest" => RuleAction::Rest,
+            "wander" => RuleAction::Wander,
+            word => return Err(self.error(format!("unknown action '{}'", word))),
+        };
+        if let Some(word) = self.peek() {
+            return Err(self.error(format!("unexpected '{}' after action", word)));
+        }
+        Ok(action)
+    }
+
+    fn rule(&mut self) -> Result<Rule, ParseError> {
+        let mut conditions = Vec::new();
+        match self.next("'when' or 'otherwise'")? {
+            "otherwise" => {}
+            "when" => {
+                conditions.push(self.condition()?);
+                loop {
+                    match self.next("'and' or 'then'")? {
+                        "and" => conditions.push(self.condition()?),
+                        "then" => break,
+                        word => return Err(self.error(format!("expected 'and' or 'then', found '{}'", word))),
+                    }
+                }
+            }
+            word => return Err(self.error(format!("rules start with 'when' or 'otherwise', found '{}'", word))),
+        }
+        Ok(Rule { conditions, action: self.action()? })
+    }
+}
+
+// *****************************************************
+
+/// A brain driven by a list of rules, checked top to bottom
+pub struct RuleBrain {
+    name: String,
+    rules: Vec<Rule>,
+}
+
+impl RuleBrain {
+    /// Parses rules from `source`, one per line; `name` is what the brain is called
+    pub fn parse(name: &str, source: &str) -> Result<Self, ParseError> {
+        let mut rules = Vec::new();
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parser = Parser { line: number + 1, words: line.split_whitespace().collect(), position: 0 };
+            rules.push(parser.rule()?);
+        }
+        Ok(RuleBrain { name: name.to_string(), rules })
+    }
+
+    /// Reads and parses a rule file, named after its path
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&path.display().to_string(), &source).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn holds(&self, condition: &Condition, view: &Surroundings, ant: &ant::Ant, rng: &mut dyn RngCore) -> bool {
+        match condition {
+            Condition::Not(inner) => !self.holds(inner, view, ant, rng),
+            Condition::Carrying(value) => ant.carrying() == *value,
+            Condition::Role(value) => ant.ant_role() == *value,
+            Condition::Is(place, value) => {
+                let (dx, dy) = place.offset(ant);
+                view.tile(dx, dy).is_some_and(|tile| tile.object() == *value)
+            }
+            Condition::Compare(quantity, comparison, number) => {
+                let value = match quantity {
+                    Quantity::Health => view.state().map_or(ant.current_health(), |state| state.health) as usize,
+                    Quantity::MaxHealth => view.state().map_or(ant.max_health(), |state| state.max_health) as usize,
+                    Quantity::Strength => view.state().map_or(ant.strength(), |state| state.strength) as usize,
+                    Quantity::Energy => view.state().map_or(constants::ANT_MAX_ENERGY, |state| state.energy) as usize,
+                    Quantity::Age => view.state().map_or(0, |state| state.age as usize),
+                    Quantity::FoodPheromone(place) => {
+                        let (dx, dy) = place.offset(ant);
+                        view.tile(dx, dy).map_or(0, |tile| tile.pheromone() as usize)
+                    }
+                    Quantity::HomePheromone(place) => {
+                        let (dx, dy) = place.offset(ant);
+                        view.tile(dx, dy).map_or(0, |tile| tile.home_pheromone() as usize)
+                    }
+                    Quantity::Ants(place) => {
+                        let (dx, dy) = place.offset(ant);
+                        view.ant_count(dx, dy)
+                    }
+                };
+                comparison.holds(value, *number)
+            }
+            Condition::Raining => view.is_raining(),
+            Condition::Night => view.is_night(),
+            Condition::AtNest => view.at_nest(),
+            Condition::Underground => view.is_underground(),
+            Condition::Blocked => {
+                let (dx, dy) = ant.direction().delta();
+                !view.is_walkable(dx, dy)
+            }
+            Condition::PredatorNear => view.nearest_predator(constants::SOLDIER_SENSE_RADIUS).is_some(),
+            Condition::PredatorAdjacent => view.nearest_predator(1).is_some(),
+            Condition::FoodNear => view.smell_food(constants::FOOD_SMELL_RADIUS).is_some(),
+            Condition::Chance(percentage) => rng.gen_range(0..100) < *percentage,
+        }
+    }
+}
+
+impl AntBrain for RuleBrain {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn decide(&self, view: &Surroundings, ant: &ant::Ant, rng: &mut dyn RngCore) -> Action {
+        let Some(rule) = self.rules.iter().find(|rule| rule.conditions.iter().all(|c| self.holds(c, view, ant, rng))) else {
+            return Action::Rest;
+        };
+
+        match rule.action {
+            RuleAction::MoveForward => Action::Move(ant.direction()),
+            RuleAction::MoveHome => match view.home_direction() {
+                Some(direction) => Action::Move(direction),
+                None => brain::wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng),
+            },
+            RuleAction::MoveHomeVector => match view.home_vector().and_then(|(dx, dy)| ant::Direction::from_delta(dx, dy)) {
+                Some(direction) => Action::Move(direction),
+                None => brain::wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng),
+            },
+            RuleAction::MoveToPredator => {
+                match view.nearest_predator(constants::SOLDIER_SENSE_RADIUS).and_then(|(dx, dy)| ant::Direction::from_delta(dx, dy)) {
+                    Some(direction) => Action::Move(direction),
+                    None => brain::wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng),
+                }
+            }
+            RuleAction::MoveToFood => match view.toward_food() {
+                Some(direction) => Action::Move(direction),
+                None => brain::wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng),
+            },
+            RuleAction::MoveAlongTrail => match view.strongest_trail(ant.direction(), constants::FOOD_TRAIL_THRESHOLD) {
+                Some(direction) => Action::Move(direction),
+                None => brain::wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng),
+            },
+            RuleAction::TurnLeft => Action::Face(ant.direction().turn_left()),
+            RuleAction::TurnRight => Action::Face(ant.direction().turn_right()),
+            RuleAction::TurnAround => Action::Face(ant.direction().reverse()),
+            RuleAction::TurnRandom => Action::Face(ant::Direction::from_bits(rng.gen_range(0..ant::Direction::COUNT))),
+            RuleAction::PickUp => Action::PickUp,
+            RuleAction::Drop => Action::Drop,
+            RuleAction::Dig => Action::Dig,
+            RuleAction::Attack => Action::Attack,
+            RuleAction::Rest => Action::Rest,
+            RuleAction::Wander => brain::wander(view, ant, constants::ANT_TURN_CHANCE_PERCENTAGE, rng),
+        }
+    }
+}
+
+// *****************************************************
+
+/// Reloads a rule file whenever its modification time changes
+pub struct RuleWatcher {
+    /// The role the rules are for, None for every role
+    pub role: Option<AntRole>,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl RuleWatcher {
+    /// Watches `path`, starting from its current modification time
+    pub fn new(role: Option<AntRole>, path: PathBuf) -> Self {
+        let modified = Self::modified(&path);
+        RuleWatcher { role, path, modified }
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    /// Some(..) once the file has changed since the last poll
+    pub fn poll(&mut self) -> Option<Result<RuleBrain, String>> {
+        let modified = Self::modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(RuleBrain::load(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> ParseError {
+        match RuleBrain::parse("test", source) {
+            Ok(_) => panic!("parsed: {}", source),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn parses_the_example_rules() {
+        let source = include_str!("../rules/forager.rules");
+        let brain = RuleBrain::parse("forager", source).unwrap();
+        assert_eq!(brain.name(), "forager");
+        assert!(!brain.rules.is_empty());
+    }
+
+    #[test]
+    fn rejects_an_unknown_condition() {
+        let e = error("# comment\nwhen night then rest\nwhen sleepy then rest\n");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "unknown condition 'sleepy'");
+    }
+
+    #[test]
+    fn rejects_a_missing_then() {
+        let e = error("otherwise wander\n\nwhen night rest");
+        assert_eq!(e.line, 3);
+        assert_eq!(e.message, "expected 'and' or 'then', found 'rest'");
+    }
+
+    #[test]
+    fn rejects_a_bad_number() {
+        let e = error("when health > lots then attack");
+        assert_eq!(e.line, 1);
+        assert_eq!(e.message, "expected a number, found 'lots'");
+        assert_eq!(error("when chance 2x then dig").message, "expected a number, found '2x'");
+    }
+}
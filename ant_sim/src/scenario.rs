@@ -0,0 +1,419 @@
+//! Scenario files: a starting world plus objectives the colony has to meet.
+//!
+//! ```text
+//!   # Comments start with '#'
+//!   name = Forager                  shown in the HUD
+//!   seed = 7                        --seed on the command line still wins
+//!   size = 200x48                   ignored when there is a map
+//!   ants = 30
+//!   topology = torus
+//!   ticks = 6000                    time limit, needed unless every objective has a
+//!                                   deadline (default: the last of them)
+//!   wave_every = 500                a wave of predators every 500 ticks...
+//!   wave_spiders = 2                ...of this many spiders (default 1)
+//!   soldier_percentage = 40         any behaviour parameter, as in a genome file
+//!
+//!   objective deliver 200 food within 5000 ticks
+//!   objective slay 3 predators
+//!   objective survive 3 waves
+//!   objective survive 4000 ticks
+//!
+//!   map                             optional starting map, see `World::from_map`
+//!   ..........@.........
+//!   ...X..N.....O..X....
+//!   ########.###########
+//!   end
+//! ```
+//!
+//! A scenario passes once every objective has, and fails as soon as one
+//! can't: its deadline passes, the colony dies out, or the time limit runs out
+//! with it still pending.
+
+use crate::constants;
+use crate::params::Params;
+use crate::predator::PredatorKind;
+use crate::topology::Topology;
+use crate::world::World;
+
+use std::path::Path;
+
+
+/// What an objective asks of the colony
+pub enum Goal {
+    /// Store this much food in the nest
+    Deliver(u32),
+    /// Kill this many predators
+    Slay(u32),
+    /// Still have ants at this tick
+    SurviveTicks(u64),
+    /// Still have ants when the wave after this many arrives
+    SurviveWaves(u32),
+}
+
+/// One line of a scenario's win conditions
+pub struct Objective {
+    /// What has to be done
+    pub goal: Goal,
+    /// Deadline in ticks for delivering or slaying
+    pub within: Option<u64>,
+}
+
+impl Objective {
+    // Tick by which the objective has either passed or failed, if there is one
+    fn deadline(&self, wave_every: Option<u64>) -> Option<u64> {
+        match self.goal {
+            Goal::Deliver(_) | Goal::Slay(_) => self.within,
+            Goal::SurviveTicks(ticks) => Some(ticks),
+            Goal::SurviveWaves(waves) => wave_every.map(|every| (waves as u64 + 1) * every),
+        }
+    }
+
+    // (done, needed) so far
+    fn progress(&self, world: &World, wave_every: Option<u64>) -> (u64, u64) {
+        match self.goal {
+            Goal::Deliver(food) => (world.stats.food_stored as u64, food as u64),
+            Goal::Slay(predators) => (world.stats.predators_killed as u64, predators as u64),
+            Goal::SurviveTicks(ticks) => (world.environment.tick(), ticks),
+            // A wave is survived if the colony is still there when the next one comes
+            Goal::SurviveWaves(waves) => {
+                let every = wave_every.unwrap_or(u64::MAX);
+                ((world.environment.tick() / every).saturating_sub(1), waves as u64)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Objective {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.goal {
+            Goal::Deliver(food) => write!(f, "deliver {} food", food)?,
+            Goal::Slay(predators) => write!(f, "slay {} predators", predators)?,
+            Goal::SurviveTicks(ticks) => write!(f, "survive {} ticks", ticks)?,
+            Goal::SurviveWaves(waves) => write!(f, "survive {} waves", waves)?,
+        }
+        match self.within {
+            Some(ticks) => write!(f, " within {} ticks", ticks),
+            None => Ok(()),
+        }
+    }
+}
+
+// *****************************************************
+
+/// A parsed scenario file; see the module docs for the format
+#[derive(Default)]
+pub struct Scenario {
+    /// Shown in the HUD and the report
+    pub name: String,
+    /// World seed, unless one is given on the command line
+    pub seed: Option<u64>,
+    /// World size in tiles; ignored when there is a map
+    pub size: Option<(usize, usize)>,
+    /// Starting ants
+    pub ants: Option<usize>,
+    /// How the edges of the world join
+    pub topology: Option<Topology>,
+    /// Time limit, see `time_limit`
+    pub ticks: Option<u64>,
+    /// Ticks between predator waves, None for no waves
+    pub wave_every: Option<u64>,
+    /// Spiders per wave
+    pub wave_spiders: u8,
+    /// Behaviour parameters, by `Params::GENES` name
+    pub genes: Vec<(String, u8)>,
+    /// Starting map rows, for `World::from_map`
+    pub map: Option<Vec<String>>,
+    /// Everything the colony has to do
+    pub objectives: Vec<Objective>,
+}
+
+impl Scenario {
+    /// Parses a scenario; errors give the line they're on
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut scenario = Scenario { wave_spiders: 1, ..Default::default() };
+        let mut lines = source.lines().enumerate();
+
+        while let Some((number, line)) = lines.next() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+
+            if line == "map" {
+                let mut rows = Vec::new();
+                loop {
+                    match lines.next() {
+                        Some((_, row)) if row.trim() == "end" => break,
+                        // ' ' is an open cell, so only the line ending goes
+                        Some((_, row)) => rows.push(row.trim_end_matches(['\r', '\n']).to_string()),
+                        None => return Err(error("map without a closing 'end'".to_string())),
+                    }
+                }
+                let borrowed: Vec<&str> = rows.iter().map(String::as_str).collect();
+                World::check_map(&borrowed).map_err(error)?;
+                scenario.map = Some(rows);
+                continue;
+            }
+            if let Some(objective) = line.strip_prefix("objective ") {
+                scenario.objectives.push(parse_objective(objective).map_err(error)?);
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected 'name = value', 'objective ...' or 'map'".to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let number = || value.parse::<u64>().map_err(|_| error(format!("{} expects a number, got '{}'", key, value)));
+            match key {
+                "name" => scenario.name = value.to_string(),
+                "seed" => scenario.seed = Some(number()?),
+                "ants" => scenario.ants = Some(number()? as usize),
+                "ticks" => scenario.ticks = Some(number()?),
+                "wave_every" => scenario.wave_every = Some(number()?.max(1)),
+                "wave_spiders" => scenario.wave_spiders = number()?.min(u8::MAX as u64) as u8,
+                "topology" => scenario.topology = Some(value.parse::<Topology>().map_err(error)?),
+                "size" => {
+                    let size = value.split_once(['x', 'X']).and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+                    scenario.size = Some(size.ok_or_else(|| error(format!("size expects WIDTHxHEIGHT, got '{}'", value)))?);
+                }
+                gene => {
+                    let value = value.parse::<u8>().map_err(|_| error(format!("'{}' is not a number from 0 to 255", value)))?;
+                    Params::default().with_gene(gene, value).ok_or_else(|| error(format!("unknown setting '{}'", gene)))?;
+                    scenario.genes.push((gene.to_string(), value));
+                }
+            }
+        }
+
+        if scenario.objectives.is_empty() {
+            return Err("a scenario needs at least one objective".to_string());
+        }
+        if scenario.wave_every.is_none() && scenario.objectives.iter().any(|objective| matches!(objective.goal, Goal::SurviveWaves(_))) {
+            return Err("surviving waves needs 'wave_every'".to_string());
+        }
+        let (width, height) = scenario
+            .map
+            .as_ref()
+            .map(|rows| (rows[0].chars().count(), rows.len()))
+            .or(scenario.size)
+            .unwrap_or((constants::SIMULATION_WIDTH, constants::SIMULATION_HEIGHT));
+        if let Some(ants) = scenario.ants
+            && ants > width.saturating_mul(height).saturating_mul(constants::MAX_ANTS_PER_TILE)
+        {
+            return Err(format!("{} ants don't fit on a {}x{} world", ants, width, height));
+        }
+        if scenario.time_limit().is_none() {
+            return Err("an objective has no deadline ('within N ticks'), so the scenario needs 'ticks'".to_string());
+        }
+        Ok(scenario)
+    }
+
+    /// Reads and parses a scenario file, naming it after the file if it has no `name`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut scenario = Self::parse(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if scenario.name.is_empty() {
+            scenario.name = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        }
+        Ok(scenario)
+    }
+
+    /// `params` with the scenario's behaviour parameters applied
+    pub fn params(&self, params: &Params) -> Params {
+        self.genes.iter().fold(params.clone(), |params, (gene, value)| params.with_gene(gene, *value).unwrap_or(params))
+    }
+
+    /// How long a run of the scenario lasts: its `ticks`, or else the last
+    /// objective deadline if every objective has one. `parse` rejects scenarios
+    /// with neither, so this is only None for ones built by hand.
+    pub fn time_limit(&self) -> Option<u64> {
+        self.ticks.or_else(|| {
+            let deadlines: Option<Vec<u64>> = self.objectives.iter().map(|objective| objective.deadline(self.wave_every)).collect();
+            deadlines?.into_iter().max()
+        })
+    }
+}
+
+fn parse_objective(text: &str) -> Result<Objective, String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let count = |word: &str| word.parse::<u64>().map_err(|_| format!("expected a number, found '{}'", word));
+    let (goal, rest) = match words[..] {
+        ["deliver", food, "food", ref rest @ ..] => (Goal::Deliver(count(food)? as u32), rest),
+        ["slay", predators, "predators" | "predator", ref rest @ ..] => (Goal::Slay(count(predators)? as u32), rest),
+        ["survive", ticks, "ticks"] => (Goal::SurviveTicks(count(ticks)?), &[][..]),
+        ["survive", waves, "waves" | "wave"] => (Goal::SurviveWaves(count(waves)? as u32), &[][..]),
+        _ => {
+            return Err(format!(
+                "unknown objective '{}' (expected deliver N food, slay N predators, survive N ticks or survive N waves)",
+                text
+            ));
+        }
+    };
+    let within = match rest {
+        [] => None,
+        ["within", ticks, "ticks"] => Some(count(ticks)?),
+        _ => return Err(format!("expected 'within N ticks' after the objective, found '{}'", rest.join(" "))),
+    };
+    Ok(Objective { goal, within })
+}
+
+// *****************************************************
+
+/// Where an objective stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Not decided yet
+    Pending,
+    /// Met on this tick
+    Passed(u64),
+    /// Missed on this tick
+    Failed(u64),
+}
+
+/// Runs a scenario alongside the world: sends in the predator waves and keeps
+/// score of the objectives
+pub struct Referee<'a> {
+    scenario: &'a Scenario,
+    statuses: Vec<Status>,
+}
+
+impl<'a> Referee<'a> {
+    /// Every objective starts out pending
+    pub fn new(scenario: &'a Scenario) -> Self {
+        Referee { scenario, statuses: vec![Status::Pending; scenario.objectives.len()] }
+    }
+
+    // Getters
+    /// One per objective, in the scenario's order
+    pub fn statuses(&self) -> &[Status] {
+        &self.statuses
+    }
+
+    /// Some(true) once every objective has passed, Some(false) once any has failed
+    pub fn outcome(&self) -> Option<bool> {
+        if self.statuses.iter().any(|status| matches!(status, Status::Failed(_))) {
+            Some(false)
+        } else if self.statuses.iter().all(|status| matches!(status, Status::Passed(_))) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Call after every step. Once the outcome is decided nothing more happens.
+    pub fn update(&mut self, world: &mut World, rng: &mut impl rand::Rng) {
+        if self.outcome().is_some() {
+            return;
+        }
+        let tick = world.environment.tick();
+        let wave_every = self.scenario.wave_every;
+        let wiped_out = world.ants().is_empty();
+
+        for (objective, status) in self.scenario.objectives.iter().zip(self.statuses.iter_mut()) {
+            if *status != Status::Pending {
+                continue;
+            }
+            let (done, needed) = objective.progress(world, wave_every);
+            *status = if wiped_out || objective.within.is_some_and(|within| tick > within && done < needed) {
+                Status::Failed(tick)
+            } else if done >= needed {
+                Status::Passed(tick)
+            } else {
+                Status::Pending
+            };
+        }
+
+        // Spiders drop in anywhere above ground; a spot that's taken is skipped
+        if let Some(every) = wave_every
+            && tick.is_multiple_of(every)
+        {
+            for _ in 0..self.scenario.wave_spiders {
+                let x = rng.gen_range(0..world.width);
+                let y = rng.gen_range(0..world.ground_top);
+                world.add_predator(PredatorKind::Spider, x, y);
+            }
+        }
+
+        if self.outcome().is_none() && self.scenario.time_limit().is_some_and(|limit| tick >= limit) {
+            self.finish(world);
+        }
+    }
+
+    /// Fails whatever is still pending, for when the run is over early. Does
+    /// nothing once the outcome is decided, so the report shows what was left.
+    pub fn finish(&mut self, world: &World) {
+        if self.outcome().is_some() {
+            return;
+        }
+        for status in self.statuses.iter_mut().filter(|status| **status == Status::Pending) {
+            *status = Status::Failed(world.environment.tick());
+        }
+    }
+
+    /// One line for the HUD, e.g. "Forager: deliver 200 food 35/200 | survive 3 waves passed"
+    pub fn progress(&self, world: &World) -> String {
+        let objectives: Vec<String> = self
+            .scenario
+            .objectives
+            .iter()
+            .zip(&self.statuses)
+            .map(|(objective, status)| match status {
+                Status::Pending => {
+                    let (done, needed) = objective.progress(world, self.scenario.wave_every);
+                    format!("{} {}/{}", objective, done, needed)
+                }
+                Status::Passed(_) => format!("{} passed", objective),
+                Status::Failed(_) => format!("{} FAILED", objective),
+            })
+            .collect();
+        let outcome = match self.outcome() {
+            Some(true) => " -- PASSED",
+            Some(false) => " -- FAILED",
+            None => "",
+        };
+        format!("{}: {}{}", self.scenario.name, objectives.join(" | "), outcome)
+    }
+
+    /// One line per objective with how it ended, for after a headless run
+    pub fn report(&self) -> Vec<String> {
+        let mut lines = vec![format!("Scenario {}: {}", self.scenario.name, if self.outcome() == Some(true) { "passed" } else { "failed" })];
+        for (objective, status) in self.scenario.objectives.iter().zip(&self.statuses) {
+            lines.push(match status {
+                Status::Pending => format!("  {}: pending", objective),
+                Status::Passed(tick) => format!("  {}: passed at tick {}", objective, tick),
+                Status::Failed(tick) => format!("  {}: failed at tick {}", objective, tick),
+            });
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_limit_is_the_last_deadline() {
+        let scenario = Scenario::parse("wave_every = 300\nobjective deliver 5 food within 1000 ticks\nobjective survive 3 waves\n").unwrap();
+        assert_eq!(scenario.time_limit(), Some(1200));
+    }
+
+    #[test]
+    fn map_rows_keep_open_edges() {
+        let scenario = Scenario::parse("ticks = 10\nobjective slay 1 predator\nmap\n  N X \r\n######\nend\n").unwrap();
+        assert_eq!(scenario.map, Some(vec!["  N X ".to_string(), "######".to_string()]));
+    }
+
+    #[test]
+    fn ants_must_fit_on_the_world() {
+        let objective = "ticks = 10\nobjective slay 1 predator\n";
+        assert!(Scenario::parse(&format!("{}ants = 4000000000\n", objective)).is_err());
+        assert!(Scenario::parse(&format!("{}size = 10x10\nants = 201\n", objective)).is_err());
+        assert_eq!(Scenario::parse(&format!("{}size = 10x10\nants = 200\n", objective)).unwrap().ants, Some(200));
+    }
+
+    #[test]
+    fn objectives_without_a_deadline_need_ticks() {
+        assert!(Scenario::parse("objective deliver 5 food within 1000 ticks\nobjective slay 3 predators\n").is_err());
+        let scenario = Scenario::parse("ticks = 500\nobjective slay 3 predators\n").unwrap();
+        assert_eq!(scenario.time_limit(), Some(500));
+    }
+}
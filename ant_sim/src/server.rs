@@ -0,0 +1,366 @@
+//! Serves the running simulation over TCP, one JSON object per line each way.
+//!
+//! Requests carry a "cmd":
+//! ```text
+//!   {"cmd":"grid"}                             the whole grid, one string per row
+//!   {"cmd":"stats"}                            current stats
+//!   {"cmd":"subscribe"} / {"cmd":"unsubscribe"} per-tick deltas, stats and events
+//!   {"cmd":"pause"} / {"cmd":"resume"} / {"cmd":"step"}
+//!   {"cmd":"place_food","x":X,"y":Y}
+//!   {"cmd":"spawn_ant","x":X,"y":Y,"role":"Soldier"}   role defaults to Worker
+//! ```
+//!
+//! Replies are `{"type":"grid"|"stats"|"ok"|"error",...}`; subscribers also get
+//! ```text
+//!   {"type":"tick","tick":N,"stats":{...},"changes":[[x,y,"c"],...],"events":[...]}
+//! ```
+//! where each change is a cell whose glyph differs from the previous tick.
+
+use crate::ant::{Ant, AntRole};
+use crate::json::Json;
+use crate::world::World;
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+
+// A client sending a longer line is dropped
+const MAX_LINE_LENGTH: usize = 64 * 1024;
+// A client that lets this much of our output pile up unread is dropped
+const MAX_OUTGOING: usize = 4 * 1024 * 1024;
+
+struct Client {
+    stream: TcpStream,
+    pending: Vec<u8>,  // Received bytes not yet ending in a newline
+    outgoing: Vec<u8>, // Replies and ticks the socket hasn't taken yet
+    subscribed: bool,
+    open: bool,
+}
+
+impl Client {
+    // Queues a line; `flush` sends it without waiting on the client
+    fn send(&mut self, message: &Json) {
+        self.outgoing.extend_from_slice(format!("{}\n", message).as_bytes());
+        if self.outgoing.len() > MAX_OUTGOING {
+            self.open = false;
+        }
+    }
+
+    // Writes as much of the queue as the socket will take right now
+    fn flush(&mut self) {
+        let mut written = 0;
+        while written < self.outgoing.len() {
+            match self.stream.write(&self.outgoing[written..]) {
+                Ok(0) => {
+                    self.open = false;
+                    break;
+                }
+                Ok(count) => written += count,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.open = false;
+                    break;
+                }
+            }
+        }
+        self.outgoing.drain(..written);
+    }
+
+    // Complete lines received so far
+    fn read_lines(&mut self) -> Vec<String> {
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => {
+                    self.open = false;
+                    break;
+                }
+                Ok(count) => {
+                    self.pending.extend_from_slice(&buffer[..count]);
+                    // Take at most about a line's worth per poll, and give up on
+                    // a client that has sent that much without ending a line
+                    if self.pending.len() > MAX_LINE_LENGTH {
+                        if !self.pending.contains(&b'\n') {
+                            self.open = false;
+                            return Vec::new();
+                        }
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.open = false;
+                    break;
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            if end > MAX_LINE_LENGTH {
+                self.open = false;
+                return lines;
+            }
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim().to_string());
+        }
+        if self.pending.len() > MAX_LINE_LENGTH {
+            self.open = false;
+        }
+        lines
+    }
+}
+
+// *****************************************************
+
+/// The listening socket and every connected client
+pub struct Server {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    frame: Vec<char>, // Glyphs sent with the last tick, to diff against
+    /// Set by the "pause" request; the world only steps on "step"
+    pub paused: bool,
+    step_requested: bool,
+}
+
+impl Server {
+    /// Starts listening on `address`, diffing ticks against `world` as it is now
+    pub fn bind(address: &str, world: &World) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Server { listener, clients: Vec::new(), frame: glyphs(world), paused: false, step_requested: false })
+    }
+
+    /// The address actually bound, e.g. after asking for port 0
+    pub fn local_address(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Whether the world should advance this tick
+    pub fn should_step(&mut self) -> bool {
+        !self.paused || std::mem::take(&mut self.step_requested)
+    }
+
+    /// Accepts new clients and answers every request that has come in
+    pub fn poll(&mut self, world: &mut World) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if stream.set_nonblocking(true).is_ok() {
+                        self.clients.push(Client { stream, pending: Vec::new(), outgoing: Vec::new(), subscribed: false, open: true });
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => break,
+            }
+        }
+
+        for c in 0..self.clients.len() {
+            for line in self.clients[c].read_lines() {
+                if line.is_empty() {
+                    continue;
+                }
+                let reply = match Json::parse(&line) {
+                    Ok(request) => self.handle(c, &request, world),
+                    Err(e) => error(e),
+                };
+                self.clients[c].send(&reply);
+            }
+            self.clients[c].flush();
+        }
+        self.clients.retain(|client| client.open);
+    }
+
+    fn handle(&mut self, client: usize, request: &Json, world: &mut World) -> Json {
+        let position = || match (request.get("x").and_then(Json::as_u64), request.get("y").and_then(Json::as_u64)) {
+            (Some(x), Some(y)) if (x as usize) < world.width && (y as usize) < world.height => Ok((x as usize, y as usize)),
+            _ => Err(error("expected \"x\" and \"y\" inside the world")),
+        };
+
+        match request.get("cmd").and_then(Json::as_str) {
+            Some("grid") => grid(world),
+            Some("stats") => {
+                let mut reply = stats(world);
+                reply.push("type", "stats".into());
+                reply
+            }
+            Some("subscribe") => {
+                self.clients[client].subscribed = true;
+                ok()
+            }
+            Some("unsubscribe") => {
+                self.clients[client].subscribed = false;
+                ok()
+            }
+            Some("pause") => {
+                self.paused = true;
+                ok()
+            }
+            Some("resume") => {
+                self.paused = false;
+                ok()
+            }
+            Some("step") => {
+                self.step_requested = true;
+                ok()
+            }
+            Some("place_food") => match position() {
+                Ok((x, y)) if world.place_food(x, y) => ok(),
+                Ok(_) => error("can't place food there"),
+                Err(reply) => reply,
+            },
+            Some("spawn_ant") => {
+                let (x, y) = match position() {
+                    Ok(position) => position,
+                    Err(reply) => return reply,
+                };
+                let role = match request.get("role").and_then(Json::as_str).map(str::parse::<AntRole>) {
+                    None => AntRole::Worker,
+                    Some(Ok(role)) => role,
+                    Some(Err(e)) => return error(e.to_string()),
+                };
+                match world.add_ant(x, y, Ant::with_role(role)) {
+                    Some(id) => {
+                        let mut reply = ok();
+                        reply.push("ant", id.into());
+                        reply
+                    }
+                    None => error("can't place an ant there"),
+                }
+            }
+            Some(command) => error(format!("unknown command '{}'", command)),
+            None => error("expected a \"cmd\""),
+        }
+    }
+
+    /// Sends subscribers what changed since the last tick
+    pub fn broadcast_tick(&mut self, world: &World) {
+        let frame = glyphs(world);
+        if self.clients.iter().any(|client| client.subscribed) {
+            let changes: Vec<Json> = frame
+                .iter()
+                .zip(&self.frame)
+                .enumerate()
+                .filter(|(_, (new, old))| new != old)
+                .map(|(i, (glyph, _))| vec![Json::from(i % world.width), Json::from(i / world.width), Json::from(glyph.to_string())].into())
+                .collect();
+            let message = Json::object([
+                ("type", "tick".into()),
+                ("tick", world.environment.tick().into()),
+                ("stats", stats(world)),
+                ("changes", changes.into()),
+                ("events", world.events().iter().map(|event| event.to_json()).collect::<Vec<_>>().into()),
+            ]);
+            for client in self.clients.iter_mut().filter(|client| client.subscribed) {
+                client.send(&message);
+                client.flush();
+            }
+            self.clients.retain(|client| client.open);
+        }
+        self.frame = frame;
+    }
+}
+
+fn glyphs(world: &World) -> Vec<char> {
+    (0..world.height).flat_map(|y| (0..world.width).map(move |x| world.glyph(x, y))).collect()
+}
+
+fn grid(world: &World) -> Json {
+    let rows: Vec<String> = (0..world.height).map(|y| (0..world.width).map(|x| world.glyph(x, y)).collect()).collect();
+    Json::object([
+        ("type", "grid".into()),
+        ("tick", world.environment.tick().into()),
+        ("width", world.width.into()),
+        ("height", world.height.into()),
+        ("rows", rows.into()),
+    ])
+}
+
+fn stats(world: &World) -> Json {
+    Json::object([
+        ("tick", world.environment.tick().into()),
+        ("ants", world.ants().len().into()),
+        ("food_stored", world.stats.food_stored.into()),
+        ("ants_killed", world.stats.ants_killed.into()),
+        ("predators_killed", world.stats.predators_killed.into()),
+        ("night", world.environment.is_night().into()),
+        ("raining", world.environment.is_raining().into()),
+    ])
+}
+
+fn ok() -> Json {
+    Json::object([("type", "ok".into())])
+}
+
+fn error(message: impl Into<String>) -> Json {
+    Json::object([("type", "error".into()), ("message", message.into().into())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Params;
+    use crate::world::WorldSize;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::io::{BufRead, BufReader};
+
+    fn setup() -> (Server, World, BufReader<TcpStream>) {
+        let world = World::from_map(&mut StdRng::seed_from_u64(1), &Params::default(), &WorldSize::default(), &["..X.", "####"]).unwrap();
+        let server = Server::bind("127.0.0.1:0", &world).unwrap();
+        let client = TcpStream::connect(server.local_address().unwrap()).unwrap();
+        client.set_read_timeout(Some(std::time::Duration::from_millis(10))).unwrap();
+        (server, world, BufReader::new(client))
+    }
+
+    // Polls the server until the client has a whole line, or the connection ends
+    fn receive(server: &mut Server, world: &mut World, client: &mut BufReader<TcpStream>) -> Option<Json> {
+        let mut line = String::new();
+        for _ in 0..500 {
+            server.poll(world);
+            match client.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) if line.ends_with('\n') => return Some(Json::parse(&line).unwrap()),
+                Ok(_) => {}
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(_) => return None,
+            }
+        }
+        panic!("no reply");
+    }
+
+    fn request(server: &mut Server, world: &mut World, client: &mut BufReader<TcpStream>, line: &str) -> Option<Json> {
+        client.get_mut().write_all(format!("{}\n", line).as_bytes()).unwrap();
+        receive(server, world, client)
+    }
+
+    #[test]
+    fn answers_stats() {
+        let (mut server, mut world, mut client) = setup();
+        let reply = request(&mut server, &mut world, &mut client, r#"{"cmd":"stats"}"#).unwrap();
+        assert_eq!(reply.get("type").and_then(Json::as_str), Some("stats"));
+        assert_eq!(reply.get("ants").and_then(Json::as_u64), Some(1));
+    }
+
+    #[test]
+    fn subscribers_get_each_tick() {
+        let (mut server, mut world, mut client) = setup();
+        let reply = request(&mut server, &mut world, &mut client, r#"{"cmd":"subscribe"}"#).unwrap();
+        assert_eq!(reply.get("type").and_then(Json::as_str), Some("ok"));
+
+        world.step(&mut StdRng::seed_from_u64(1));
+        server.broadcast_tick(&world);
+        let tick = receive(&mut server, &mut world, &mut client).unwrap();
+        assert_eq!(tick.get("type").and_then(Json::as_str), Some("tick"));
+        assert_eq!(tick.get("tick").and_then(Json::as_u64), Some(1));
+    }
+
+    #[test]
+    fn drops_a_client_that_never_ends_its_line() {
+        let (mut server, mut world, mut client) = setup();
+        client.get_mut().write_all(&vec![b' '; MAX_LINE_LENGTH + 10_000]).unwrap();
+        assert!(receive(&mut server, &mut world, &mut client).is_none());
+        assert!(server.clients.is_empty());
+    }
+}
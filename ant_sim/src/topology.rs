@@ -0,0 +1,101 @@
+/// What happens at the edges of the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topology {
+    /// Every edge is a hard boundary
+    Walls,
+    /// Left and right edges wrap around, top and bottom are walls
+    Cylinder,
+    /// Both axes wrap around
+    Torus,
+}
+
+impl std::str::FromStr for Topology {
+    type Err = String;
+
+    /// "walls", "cylinder" or "torus", ignoring case
+    fn from_str(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "walls" => Ok(Topology::Walls),
+            "cylinder" => Ok(Topology::Cylinder),
+            "torus" => Ok(Topology::Torus),
+            _ => Err(format!("unknown topology '{}' (expected walls, cylinder or torus)", name)),
+        }
+    }
+}
+
+impl Topology {
+    /// Whether the left and right edges join
+    pub fn wraps_x(self) -> bool {
+        !matches!(self, Topology::Walls)
+    }
+
+    /// Whether the top and bottom edges join
+    pub fn wraps_y(self) -> bool {
+        matches!(self, Topology::Torus)
+    }
+
+    /// (x, y) moved by (dx, dy) on a width x height grid, None if that leaves it
+    pub fn offset(self, (x, y): (usize, usize), (dx, dy): (isize, isize), (width, height): (usize, usize)) -> Option<(usize, usize)> {
+        Some((step(x, dx, width, self.wraps_x())?, step(y, dy, height, self.wraps_y())?))
+    }
+
+    /// Shortest signed displacement from one cell to another
+    pub fn displacement(self, from: (usize, usize), to: (usize, usize), (width, height): (usize, usize)) -> (isize, isize) {
+        let dx = to.0 as isize - from.0 as isize;
+        let dy = to.1 as isize - from.1 as isize;
+        (shortest(dx, width, self.wraps_x()), shortest(dy, height, self.wraps_y()))
+    }
+
+    /// Brings a remembered offset back to its shortest form after crossing a wrapping edge
+    pub fn shorten(self, (dx, dy): (isize, isize), (width, height): (usize, usize)) -> (isize, isize) {
+        (shortest(dx, width, self.wraps_x()), shortest(dy, height, self.wraps_y()))
+    }
+}
+
+fn step(position: usize, delta: isize, length: usize, wraps: bool) -> Option<usize> {
+    if wraps {
+        Some((position as isize + delta).rem_euclid(length as isize) as usize)
+    } else {
+        position.checked_add_signed(delta).filter(|&moved| moved < length)
+    }
+}
+
+fn shortest(delta: isize, length: usize, wraps: bool) -> isize {
+    let length = length as isize;
+    if !wraps || length == 0 {
+        return delta;
+    }
+    let delta = delta.rem_euclid(length);
+    if delta > length / 2 { delta - length } else { delta }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: (usize, usize) = (10, 6);
+
+    #[test]
+    fn walls_stop_at_the_edges() {
+        assert_eq!(Topology::Walls.offset((0, 0), (-1, 0), SIZE), None);
+        assert_eq!(Topology::Walls.offset((9, 5), (0, 1), SIZE), None);
+        assert_eq!(Topology::Walls.offset((3, 2), (1, -1), SIZE), Some((4, 1)));
+        assert_eq!(Topology::Walls.displacement((9, 0), (0, 5), SIZE), (-9, 5));
+    }
+
+    #[test]
+    fn cylinder_wraps_only_sideways() {
+        assert_eq!(Topology::Cylinder.offset((0, 3), (-1, 0), SIZE), Some((9, 3)));
+        assert_eq!(Topology::Cylinder.offset((9, 3), (2, 0), SIZE), Some((1, 3)));
+        assert_eq!(Topology::Cylinder.offset((4, 0), (0, -1), SIZE), None);
+        assert_eq!(Topology::Cylinder.displacement((9, 0), (0, 5), SIZE), (1, 5));
+    }
+
+    #[test]
+    fn torus_wraps_both_ways() {
+        assert_eq!(Topology::Torus.offset((0, 0), (-1, -1), SIZE), Some((9, 5)));
+        assert_eq!(Topology::Torus.displacement((9, 5), (0, 0), SIZE), (1, 1));
+        assert_eq!(Topology::Torus.displacement((1, 1), (6, 4), SIZE), (5, 3));
+        assert_eq!(Topology::Torus.shorten((-8, 5), SIZE), (2, -1));
+    }
+}
@@ -1,67 +1,45 @@
 use crate::ant;
+use crate::ant_store::{AntId, AntStore};
+use crate::brain::{Action, Brains, DefaultBrain, Surroundings};
 use crate::constants;
+use crate::environment::Environment;
+use crate::events::{Event, EventKind, Observer};
+use crate::occupancy::Occupancy;
+use crate::packed::packed_enum;
+use crate::params::Params;
+use crate::predator::{Predator, PredatorKind};
+use crate::rng::SplitMix64;
+use crate::topology::Topology;
 
+use rand::Rng;
 
-pub enum Objects {
-    None,
-    Ant,
-    Food,
-    Obstacle,
-    Object4,
-    Object5,
-    Object6,
-    Object7,
-}
-
-impl From<Objects> for String {
-    fn from(object: Objects) -> Self {
-        match object {
-            Objects::None => "None".to_string(),
-            Objects::Ant => "Ant".to_string(),
-            Objects::Food => "Food".to_string(),
-            Objects::Obstacle => "Obstacle".to_string(),
-            Objects::Object4 => "Object4".to_string(),
-            Objects::Object5 => "Object5".to_string(),
-            Objects::Object6 => "Object6".to_string(),
-            Objects::Object7 => "Object7".to_string(),
-        }
-    }
-}
-
-impl From<u8> for Objects {
-    fn from(value: u8) -> Self {
-        match value & 0b111 {
-            0 => Objects::None,
-            1 => Objects::Ant,
-            2 => Objects::Food,
-            3 => Objects::Obstacle,
-            4 => Objects::Object4,
-            5 => Objects::Object5,
-            6 => Objects::Object6,
-            7 => Objects::Object7,
-            _ => unreachable!(),
-        }
-    }
-}
 
-impl From<Objects> for u8 {
-    fn from(object: Objects) -> Self {
-        match object {
-            Objects::None => 0,
-            Objects::Ant => 1,
-            Objects::Food => 2,
-            Objects::Obstacle => 3,
-            Objects::Object4 => 4,
-            Objects::Object5 => 5,
-            Objects::Object6 => 6,
-            Objects::Object7 => 7,
-        }
+packed_enum! {
+    /// What occupies a tile, if anything
+    pub enum Objects {
+        /// Open sky or tunnel
+        None,
+        /// Unused; ants are kept in their own index, not the tiles
+        Object1,
+        /// Food waiting to be picked up
+        Food,
+        /// Solid ground
+        Obstacle,
+        /// Loose soil dumped by ants, which settles downwards
+        Soil,
+        /// Flood water, which ants can't walk through
+        Water,
+        /// The tile a predator is on
+        Predator,
+        /// Unused
+        Object7,
     }
 }
 
 // *****************************************************
 
-#[derive(Debug, Clone)]
+/// One grid cell packed into a byte: an object and food and home pheromone levels
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tile(u8);
 
 impl Tile {
@@ -70,6 +48,7 @@ impl Tile {
     const FOOD_PHEROMONE_LEVEL_MASK: u8 = 0b0001_1000;
     const HOME_PHEROMONE_LEVEL_MASK: u8 = 0b0000_0111;
 
+    /// An open tile with no pheromone, holding food `food_chance_percentage` % of the time
     pub fn new(rng: &mut impl rand::Rng, food_chance_percentage: u8) -> Self {
         let mut tile = Tile(0);
 
@@ -80,23 +59,44 @@ impl Tile {
     }
 
     // Getters
+    /// Character the tile is drawn with, ignoring any ants on it
+    pub fn glyph(&self) -> char {
+        match self.object() {
+            Objects::Obstacle => '#',
+            Objects::Food => 'O',
+            Objects::Soil => ':',
+            Objects::Water => '~',
+            Objects::Predator => '@',
+            _ => '.',
+        }
+    }
+    /// Whether `object` is on the tile
+    pub fn is(&self, object: Objects) -> bool {
+        (self.0 & Self::HAS_OBJECT_MASK) >> 5 == object as u8
+    }
+    /// What is on the tile
     pub fn object(&self) -> Objects {
-        Objects::from((self.0 & Self::HAS_OBJECT_MASK) >> 5)
+        Objects::from_bits((self.0 & Self::HAS_OBJECT_MASK) >> 5)
     }
+    /// Food trail strength, 0 to 3
     pub fn pheromone(&self) -> u8 {
         (self.0 & Self::FOOD_PHEROMONE_LEVEL_MASK) >> 3
     }
+    /// Home trail strength, 0 to 7
     pub fn home_pheromone(&self) -> u8 {
         self.0 & Self::HOME_PHEROMONE_LEVEL_MASK
     }
 
     // Setters
+    /// Puts `object` on the tile, replacing whatever was there
     pub fn set_object(&mut self, object: Objects) {
         self.0 = (self.0 & !Self::HAS_OBJECT_MASK) | ((object as u8) << 5);
     }
+    /// Sets the food trail strength, capped at 3
     pub fn set_pheromone(&mut self, level: u8) {
         self.0 = (self.0 & !Self::FOOD_PHEROMONE_LEVEL_MASK) | ((level.min(3) & 0b11) << 3);
     }
+    /// Sets the home trail strength, capped at 7
     pub fn set_home_pheromone(&mut self, level: u8) {
         self.0 = (self.0 & !Self::HOME_PHEROMONE_LEVEL_MASK) | (level.min(7) & 0b111);
     }
@@ -104,48 +104,284 @@ impl Tile {
 
 // *****************************************************
 
-pub struct AntUnit {
-    pub ant: ant::Ant,
-    pub x: usize,
-    pub y: usize,
+/// Running totals for the whole simulation
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    /// Food delivered to the nest
+    pub food_stored: u32,
+    /// Ants that died
+    pub ants_killed: u32,
+    /// Predators the soldiers killed
+    pub predators_killed: u32,
 }
 
 // *****************************************************
 
+/// Dimensions and population of a freshly generated world
+#[derive(Debug, Clone)]
+pub struct WorldSize {
+    /// Width in tiles
+    pub width: usize,
+    /// Height in tiles
+    pub height: usize,
+    /// Rows of solid ground at the bottom
+    pub ground_height: usize,
+    /// Ants to start with
+    pub ants: usize,
+    /// Packed ants only, without the wide per-ant record
+    pub compact: bool,
+}
+
+impl WorldSize {
+    /// Ground takes up the same share of the height as in the default world
+    pub fn new(width: usize, height: usize, ants: usize) -> Self {
+        let ground_height = (height * constants::GROUND_HEIGHT / constants::SIMULATION_HEIGHT).clamp(1, height.saturating_sub(2));
+        WorldSize { width, height, ground_height, ants, compact: false }
+    }
+}
+
+impl Default for WorldSize {
+    fn default() -> Self {
+        WorldSize {
+            width: constants::SIMULATION_WIDTH,
+            height: constants::SIMULATION_HEIGHT,
+            ground_height: constants::GROUND_HEIGHT,
+            ants: constants::STARTING_ANT_COUNT,
+            compact: false,
+        }
+    }
+}
+
+// *****************************************************
+
+/// The grid, the colony living in it and the predators hunting it
 pub struct World {
-    pub grid: [Tile; constants::SIMULATION_HEIGHT * constants::SIMULATION_WIDTH],
-    pub ants: Vec<AntUnit>
+    /// Width in tiles
+    pub width: usize,
+    /// Height in tiles
+    pub height: usize,
+    /// First row of solid ground
+    pub ground_top: usize,
+    grid: Vec<Tile>,
+    ants: AntStore,
+    occupancy: Occupancy, // Which ants stand on each cell, kept in step with `ants`
+    /// Spiders and antlions, dead ones included until they are cleared
+    pub predators: Vec<Predator>,
+    /// The brain each ant decides its actions with
+    pub brains: Brains,
+    /// Threads used to decide ant actions, 1 = single-threaded
+    pub threads: usize,
+    /// % chance an ant misremembers the heading of a step
+    pub path_noise: u8,
+    /// How the edges of the world connect
+    pub topology: Topology,
+    /// Time of day and weather
+    pub environment: Environment,
+    /// Running totals
+    pub stats: Stats,
+    /// Where the colony delivers its food
+    pub nest: (usize, usize),
+    events: Vec<Event>,
+    events_delivered: usize, // How many of `events` observers have already seen
+    observers: Vec<Box<dyn Observer>>,
 }
 
 impl World {
+    /// A world with the default params
     pub fn new(rng: &mut impl rand::Rng) -> Self {
-        let grid = std::array::from_fn(|_| Tile::new(rng, constants::FOOD_SPAWNING_CHANCE_PERCENTAGE));
-        let mut world = World {
-            grid,
-            ants: Vec::with_capacity(constants::STARTING_ANT_COUNT as usize),
-        };
+        Self::with_params(rng, &Params::default())
+    }
+
+    /// A world whose colony make-up and default brain follow `params`
+    pub fn with_params(rng: &mut impl rand::Rng, params: &Params) -> Self {
+        Self::generate(rng, params, &WorldSize::default())
+    }
+
+    /// A fresh world: sky over solid ground with the nest on the surface, and the
+    /// first ants, food and predators scattered around
+    pub fn generate(rng: &mut impl rand::Rng, params: &Params, size: &WorldSize) -> Self {
+        let (width, height) = (size.width, size.height);
+        let ground_top = height - size.ground_height;
+        let grid = (0..width * height).map(|_| Tile::new(rng, constants::FOOD_SPAWNING_CHANCE_PERCENTAGE)).collect();
+        let mut world = Self::with_grid(width, height, ground_top, grid, params, size);
+
+        // Add ground
+        for y in ground_top..height {
+            for x in 0..width {
+                world.add_object(x, y, Objects::Obstacle);
+            }
+        }
+
+        // Nest sits on the surface above its tunnel entrance
+        let (nest_x, nest_y) = world.nest;
+        world.add_object(nest_x, nest_y, Objects::None);
+        world.dig(nest_x, ground_top);
+        world.emit(EventKind::ColonyFounded { colony: 0 }, (nest_x, nest_y), None);
 
         // Add ants
-        for _ in 0..constants::STARTING_ANT_COUNT {
-            let x = rng.gen_range(0..constants::SIMULATION_WIDTH);
-            let y = rng.gen_range(0..constants::SIMULATION_HEIGHT - constants::GROUND_HEIGHT);
-            world.add_object(x, y, Objects::Ant);
+        for _ in 0..size.ants {
+            let x = rng.gen_range(0..width);
+            let y = rng.gen_range(0..ground_top);
+            let ant = Self::random_ant(rng, params);
+            world.add_ant(x, y, ant);
         }
-        
-        // Add ground
-        for y in constants::SIMULATION_HEIGHT - constants::GROUND_HEIGHT..constants::SIMULATION_HEIGHT {
-            for x in 0..constants::SIMULATION_WIDTH {
-                if let Some(tile) = world.get_tile_mut(x, y) {
-                    tile.set_object(Objects::Obstacle);
+
+        // Add predators: spiders anywhere above ground, antlion pits along the surface
+        for _ in 0..constants::SPIDER_COUNT {
+            let x = rng.gen_range(0..width);
+            let y = rng.gen_range(0..ground_top);
+            world.add_predator(PredatorKind::Spider, x, y);
+        }
+        for _ in 0..constants::ANTLION_COUNT {
+            let x = rng.gen_range(0..width);
+            world.add_predator(PredatorKind::Antlion, x, ground_top - 1);
+        }
+
+        world
+    }
+
+    /// A world laid out from rows of glyphs: `.` open, `#` ground, `:` soil, `O`
+    /// food, `~` water, `@` a spider, `N` the nest and `X` a worker ant. Ground
+    /// starts at the first row that is mostly solid, and the nest defaults to the
+    /// middle of the surface. A map without any `X` gets `size.ants` ants
+    /// scattered above ground, as in `generate`; `size.width` and `size.height`
+    /// are ignored.
+    pub fn from_map(rng: &mut impl rand::Rng, params: &Params, size: &WorldSize, rows: &[&str]) -> Result<Self, String> {
+        let (width, height) = Self::check_map(rows)?;
+        let rows: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        let solid = |row: &Vec<char>| row.iter().filter(|&&glyph| glyph == '#' || glyph == ':').count();
+        let ground_top = rows.iter().position(|row| solid(row) * 2 > width).unwrap_or(height - 1).max(1);
+
+        let grid = vec![Tile(0); width * height];
+        let mut world = Self::with_grid(width, height, ground_top, grid, params, size);
+        let (mut spiders, mut ants, mut nests) = (Vec::new(), Vec::new(), Vec::new());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &glyph) in row.iter().enumerate() {
+                let object = match glyph {
+                    '.' | ' ' => Objects::None,
+                    '#' => Objects::Obstacle,
+                    ':' => Objects::Soil,
+                    'O' => Objects::Food,
+                    '~' => Objects::Water,
+                    '@' => {
+                        spiders.push((x, y));
+                        Objects::None
+                    }
+                    'X' => {
+                        ants.push((x, y));
+                        Objects::None
+                    }
+                    'N' => {
+                        nests.push((x, y));
+                        Objects::None
+                    }
+                    _ => unreachable!("checked by check_map"),
+                };
+                world.add_object(x, y, object);
+            }
+        }
+        if let Some(&nest) = nests.first() {
+            world.nest = nest;
+        }
+        world.emit(EventKind::ColonyFounded { colony: 0 }, world.nest, None);
+
+        for (x, y) in ants.iter().copied() {
+            let mut ant = ant::Ant::with_role(ant::AntRole::Worker);
+            ant.set_direction(ant::Direction::from_bits(rng.gen_range(0..ant::Direction::COUNT)));
+            world.add_ant(x, y, ant);
+        }
+        if ants.is_empty() {
+            // Open tiles above ground are picked at random, giving up if the map has too few
+            let mut attempts = size.ants * 100;
+            while world.ants.len() < size.ants && attempts > 0 {
+                let x = rng.gen_range(0..width);
+                let y = rng.gen_range(0..ground_top);
+                let ant = Self::random_ant(rng, params);
+                world.add_ant(x, y, ant);
+                attempts -= 1;
+            }
+        }
+        for (x, y) in spiders {
+            world.add_predator(PredatorKind::Spider, x, y);
+        }
+
+        Ok(world)
+    }
+
+    /// Checks a map for `from_map`, returning its (width, height)
+    pub fn check_map(rows: &[&str]) -> Result<(usize, usize), String> {
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if width < 2 || rows.len() < 2 {
+            return Err("a map needs at least two rows of two tiles".to_string());
+        }
+        if width > AntStore::MAX_COORDINATE || rows.len() > AntStore::MAX_COORDINATE {
+            return Err("map too large for packed ant positions".to_string());
+        }
+        let mut nests = 0;
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("map row {} is {} tiles wide, not {}", y + 1, row.chars().count(), width));
+            }
+            for (x, glyph) in row.chars().enumerate() {
+                match glyph {
+                    '.' | ' ' | '#' | ':' | 'O' | '~' | '@' | 'X' => {}
+                    'N' => nests += 1,
+                    _ => return Err(format!("map row {}, column {}: unknown tile '{}'", y + 1, x + 1, glyph)),
                 }
             }
         }
+        match nests {
+            0 | 1 => Ok((width, rows.len())),
+            _ => Err("a map can only have one nest 'N'".to_string()),
+        }
+    }
 
-        world
+    // An empty colony on `grid`, with its nest in the middle of the surface
+    fn with_grid(width: usize, height: usize, ground_top: usize, grid: Vec<Tile>, params: &Params, size: &WorldSize) -> Self {
+        assert!(width <= AntStore::MAX_COORDINATE && height <= AntStore::MAX_COORDINATE, "world too large for packed ant positions");
+        World {
+            width,
+            height,
+            ground_top,
+            grid,
+            ants: AntStore::with_capacity(size.ants, !size.compact),
+            occupancy: Occupancy::new(width, height),
+            predators: Vec::new(),
+            brains: Brains::new(Box::new(DefaultBrain::new(params.clone()))),
+            threads: 1,
+            path_noise: 0,
+            topology: Topology::Walls,
+            environment: Environment::new(),
+            stats: Stats::default(),
+            nest: (width / 2, ground_top - 1),
+            events: Vec::new(),
+            events_delivered: 0,
+            observers: Vec::new(),
+        }
+    }
+
+    // A new ant facing a random way, a soldier `soldier_percentage` % of the time
+    fn random_ant(rng: &mut impl rand::Rng, params: &Params) -> ant::Ant {
+        let role = if rng.gen_range(0..100) < params.soldier_percentage { ant::AntRole::Soldier } else { ant::AntRole::Worker };
+        let mut ant = ant::Ant::with_role(role);
+        ant.set_direction(ant::Direction::from_bits(rng.gen_range(0..ant::Direction::COUNT)));
+        ant
     }
 
-    pub fn idx(x: usize, y: usize) -> usize {
-        y * constants::SIMULATION_WIDTH + x
+    // Getters
+    /// Every tile, row by row; see `idx`
+    pub fn grid(&self) -> &[Tile] {
+        &self.grid
+    }
+    /// The live ants. Add, move and remove them through the world, which keeps
+    /// its per-cell index of them up to date.
+    pub fn ants(&self) -> &AntStore {
+        &self.ants
+    }
+
+    /// Index into `grid()` of (x, y)
+    pub fn idx(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
     }
 
     // pub fn has_food(&self, x: usize, y: usize) -> bool {
@@ -153,19 +389,771 @@ impl World {
     //     self.grid[index].
     // }
 
+    /// Puts `object` on (x, y), if it is inside the world
     pub fn add_object(&mut self, x: usize, y: usize, object: Objects) {
-        let ant = ant::Ant::new();
-        self.ants.push(AntUnit { ant, x, y });
-        let index: usize = Self::idx(x, y);
-        self.grid[index].set_object(object);
+        if let Some(tile) = self.get_tile_mut(x, y) {
+            tile.set_object(object);
+        }
+    }
+
+    /// The cell (dx, dy) away from (x, y), wrapping around the edges the topology joins
+    pub fn neighbour(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        self.topology.offset((x, y), (dx, dy), (self.width, self.height))
+    }
+
+    /// Shortest offset from one cell to another
+    pub fn displacement(&self, from: (usize, usize), to: (usize, usize)) -> (isize, isize) {
+        self.topology.displacement(from, to, (self.width, self.height))
+    }
+
+    /// The tile at (x, y), or None outside the world
+    pub fn get_tile(&self, x: usize, y: usize) -> Option<&Tile> {
+        if x < self.width && y < self.height {
+            Some(&self.grid[self.idx(x, y)])
+        } else {
+            None
+        }
     }
 
+    /// The tile at (x, y) to change, or None outside the world
     pub fn get_tile_mut(&mut self, x: usize, y: usize) -> Option<&mut Tile> {
-        if x < constants::SIMULATION_WIDTH && y < constants::SIMULATION_HEIGHT {
-            let index: usize = Self::idx(x, y);
+        if x < self.width && y < self.height {
+            let index: usize = self.idx(x, y);
             Some(&mut self.grid[index])
         } else {
             None
         }
     }
-}
\ No newline at end of file
+
+    /// What (x, y) looks like on screen: an ant if any stand there, else the tile
+    pub fn glyph(&self, x: usize, y: usize) -> char {
+        match self.ant_count(x, y) {
+            0 => self.grid[self.idx(x, y)].glyph(),
+            _ => 'X',
+        }
+    }
+
+    /// Drops food on an empty tile away from the nest
+    pub fn place_food(&mut self, x: usize, y: usize) -> bool {
+        if !self.is_empty(x, y) || self.is_nest(x, y) {
+            return false;
+        }
+        self.add_object(x, y, Objects::Food);
+        true
+    }
+
+    // Ants
+
+    /// Whether an ant may stand on (x, y): on the grid, not solid and not crowded
+    pub fn is_walkable(&self, x: usize, y: usize) -> bool {
+        match self.get_tile(x, y) {
+            Some(tile) => !matches!(tile.object(), Objects::Obstacle | Objects::Soil | Objects::Water | Objects::Predator) && !self.occupancy.is_full(x, y),
+            None => false,
+        }
+    }
+
+    /// Returns the new ant's id, or None if it couldn't be placed
+    pub fn add_ant(&mut self, x: usize, y: usize, ant: ant::Ant) -> Option<AntId> {
+        if !self.is_walkable(x, y) {
+            return None;
+        }
+        let role = ant.ant_role();
+        let id = self.ants.push(ant, 0, x, y);
+        self.occupancy.insert(x, y, id);
+        self.emit(EventKind::AntSpawned { role }, (x, y), Some(id));
+        // New ants start out knowing the way home
+        let (dx, dy) = self.displacement((x, y), self.nest);
+        let home = (dx as i32, dy as i32);
+        if let Some(slot) = self.ants.slot(id)
+            && let Some(state) = self.ants.state_mut(slot)
+        {
+            state.home = home;
+        }
+        Some(id)
+    }
+
+    /// Takes the ant out of the world, returning it and where it stood
+    pub fn remove_ant(&mut self, id: AntId) -> Option<(ant::Ant, usize, usize)> {
+        let removed = self.ants.remove(id)?;
+        self.occupancy.remove(removed.1, removed.2, id);
+        Some(removed)
+    }
+
+    /// Moves the ant onto (x, y) if it can stand there. Returns false if it
+    /// can't, or the ant is dead.
+    pub fn move_ant(&mut self, id: AntId, x: usize, y: usize) -> bool {
+        match self.ants.slot(id) {
+            Some(slot) => self.move_slot(slot, x, y),
+            None => false,
+        }
+    }
+
+    fn move_slot(&mut self, slot: usize, x: usize, y: usize) -> bool {
+        if !self.is_walkable(x, y) {
+            return false;
+        }
+        if !self.occupancy.relocate(self.ants.position(slot), (x, y), self.ants.id(slot)) {
+            return false;
+        }
+        self.ants.set_position(slot, x, y);
+        true
+    }
+
+    /// The ants standing on (x, y)
+    pub fn ants_at(&self, x: usize, y: usize) -> impl Iterator<Item = AntId> + '_ {
+        self.occupancy.at(x, y)
+    }
+
+    /// How many ants stand on (x, y)
+    pub fn ant_count(&self, x: usize, y: usize) -> usize {
+        self.occupancy.count(x, y)
+    }
+
+    /// All ants whose cell lies within `radius` (euclidean) of (x, y)
+    pub fn ants_within(&self, x: usize, y: usize, radius: usize) -> Vec<AntId> {
+        // Don't reach around a wrapping axis far enough to see a cell twice
+        let reach_x = if self.topology.wraps_x() { radius.min((self.width - 1) / 2) } else { radius } as isize;
+        let reach_y = if self.topology.wraps_y() { radius.min((self.height - 1) / 2) } else { radius } as isize;
+        let radius_sq = (radius * radius) as isize;
+
+        let mut found = Vec::new();
+        for dy in -reach_y..=reach_y {
+            for dx in -reach_x..=reach_x {
+                if dx * dx + dy * dy <= radius_sq
+                    && let Some((nx, ny)) = self.neighbour(x, y, dx, dy)
+                {
+                    found.extend(self.ants_at(nx, ny));
+                }
+            }
+        }
+        found
+    }
+
+    /// Ants on (x, y) and its eight neighbours
+    pub fn ants_around(&self, x: usize, y: usize) -> Vec<AntId> {
+        let mut found = Vec::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if let Some((nx, ny)) = self.neighbour(x, y, dx, dy) {
+                    found.extend(self.ants_at(nx, ny));
+                }
+            }
+        }
+        found
+    }
+
+    // Events
+
+    /// Observers hear about events at the end of each step. Events from outside a
+    /// step (the world being generated, changes made between ticks) go out with
+    /// the next one, so observers added before the first step see the founding.
+    pub fn subscribe(&mut self, observer: Box<dyn Observer>) {
+        self.observers.push(observer);
+    }
+
+    /// Events delivered by the last step, followed by any emitted since
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    fn emit(&mut self, kind: EventKind, (x, y): (usize, usize), ant: Option<AntId>) {
+        self.events.push(Event { tick: self.environment.tick(), x, y, ant, kind });
+    }
+
+    fn notify_observers(&mut self) {
+        for observer in self.observers.iter_mut() {
+            for event in &self.events {
+                observer.notify(event);
+            }
+        }
+        self.events_delivered = self.events.len();
+    }
+
+    // Predators
+
+    /// Places a predator on (x, y) if the cell is empty and away from the nest
+    pub fn add_predator(&mut self, kind: PredatorKind, x: usize, y: usize) -> bool {
+        if !self.is_empty(x, y) || self.is_nest(x, y) {
+            return false;
+        }
+        self.add_object(x, y, Objects::Predator);
+        self.predators.push(Predator::new(kind, x, y, constants::PREDATOR_HEALTH));
+        true
+    }
+
+    /// Nearest predator within `radius` (chessboard distance)
+    pub fn nearest_predator(&self, x: usize, y: usize, radius: usize) -> Option<&Predator> {
+        self.predators
+            .iter()
+            .map(|predator| {
+                let (dx, dy) = self.displacement((x, y), (predator.x, predator.y));
+                (dx.unsigned_abs().max(dy.unsigned_abs()), predator)
+            })
+            .filter(|(distance, _)| *distance <= radius)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, predator)| predator)
+    }
+
+    /// Offset to the nearest food within `radius` (chessboard distance), not
+    /// counting (x, y) itself. Scans each row of the square around (x, y) as one
+    /// or two plain slices so it stays cheap enough for every ant every tick.
+    pub fn nearest_food(&self, x: usize, y: usize, radius: usize) -> Option<(isize, isize)> {
+        let width = self.width as isize;
+        let reach_x = if self.topology.wraps_x() { radius.min((self.width - 1) / 2) } else { radius } as isize;
+        let reach_y = if self.topology.wraps_y() { radius.min((self.height - 1) / 2) } else { radius } as isize;
+        let (left, right) = (x as isize - reach_x, x as isize + reach_x);
+        let mut best: Option<(isize, isize, isize)> = None; // dx, dy, distance
+
+        for dy in -reach_y..=reach_y {
+            let Some((_, ny)) = self.neighbour(x, y, 0, dy) else {
+                continue;
+            };
+            let row = &self.grid[ny * self.width..(ny + 1) * self.width];
+            // Cells lo..=hi of the row, `shift` maps them back to unwrapped x
+            let mut scan = |lo: isize, hi: isize, shift: isize| {
+                for (i, tile) in row[lo as usize..=hi as usize].iter().enumerate() {
+                    if tile.is(Objects::Food) {
+                        let dx = lo + i as isize + shift - x as isize;
+                        let distance = dx.abs().max(dy.abs());
+                        if distance > 0 && best.is_none_or(|(_, _, best)| distance < best) {
+                            best = Some((dx, dy, distance));
+                        }
+                    }
+                }
+            };
+            if !self.topology.wraps_x() {
+                scan(left.max(0), right.min(width - 1), 0);
+            } else if left < 0 {
+                scan(left + width, width - 1, -width);
+                scan(0, right, 0);
+            } else if right >= width {
+                scan(left, width - 1, 0);
+                scan(0, right - width, width);
+            } else {
+                scan(left, right, 0);
+            }
+        }
+        best.map(|(dx, dy, _)| (dx, dy))
+    }
+
+    // Simulation
+
+    /// Ants first all decide against the world as it stood at the start of the tick,
+    /// then their actions are applied in index order, so earlier ants win any
+    /// conflicts. The result is the same whatever `threads` is set to.
+    pub fn step(&mut self, rng: &mut impl rand::Rng) {
+        self.events.drain(..self.events_delivered);
+        self.environment.advance(rng);
+
+        let tick_seed = rng.next_u64();
+        let actions = if self.threads > 1 { self.decide_parallel(tick_seed) } else { self.decide_all(tick_seed) };
+        for (slot, action) in actions.into_iter().enumerate() {
+            self.apply_action(slot, action, rng);
+        }
+        if let Some(states) = self.ants.states_mut() {
+            for state in states {
+                state.age = state.age.saturating_add(1);
+            }
+        }
+
+        self.update_predators(rng);
+        self.settle_soil(rng);
+        self.update_weather(rng);
+        self.update_pheromones();
+        self.notify_observers();
+    }
+
+    fn decide(&self, slot: usize, tick_seed: u64) -> Action {
+        let mut rng = SplitMix64::new(tick_seed, slot as u64);
+
+        // At night most ants rest
+        if self.environment.is_night() && rng.gen_range(0..100) >= constants::NIGHT_ACTIVITY_PERCENTAGE {
+            return Action::Rest;
+        }
+        // Exhausted ants have to rest before they can do anything else
+        let state = self.ants.state(slot);
+        if state.is_some_and(|state| state.energy == 0) {
+            return Action::Rest;
+        }
+        let (x, y) = self.ants.position(slot);
+        let ant = self.ants.ant(slot);
+        let view = Surroundings::new(self, x, y, state);
+        self.brains.for_ant(ant).decide(&view, ant, &mut rng)
+    }
+
+    fn decide_all(&self, tick_seed: u64) -> Vec<Action> {
+        (0..self.ants.len()).map(|slot| self.decide(slot, tick_seed)).collect()
+    }
+
+    // Splits the grid into horizontal strips and decides each strip's ants on its own thread
+    fn decide_parallel(&self, tick_seed: u64) -> Vec<Action> {
+        let strip_height = self.height.div_ceil(self.threads);
+        let mut strips: Vec<Vec<usize>> = vec![Vec::new(); self.threads];
+        for (slot, _, (_, y)) in self.ants.iter() {
+            strips[y / strip_height].push(slot);
+        }
+
+        let mut actions: Vec<Action> = (0..self.ants.len()).map(|_| Action::Rest).collect();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = strips
+                .iter()
+                .map(|strip| scope.spawn(move || strip.iter().map(|&slot| (slot, self.decide(slot, tick_seed))).collect::<Vec<_>>()))
+                .collect();
+            for handle in handles {
+                for (slot, action) in handle.join().expect("ant decision thread panicked") {
+                    actions[slot] = action;
+                }
+            }
+        });
+        actions
+    }
+
+    /// Whether (x, y) is the nest or next to it
+    pub fn is_nest(&self, x: usize, y: usize) -> bool {
+        let (dx, dy) = self.displacement((x, y), self.nest);
+        dx.abs() <= 1 && dy.abs() <= 1
+    }
+
+    fn update_pheromones(&mut self) {
+        if self.environment.tick().is_multiple_of(constants::PHEROMONE_DECAY_TICKS) {
+            for tile in self.grid.iter_mut() {
+                tile.set_pheromone(tile.pheromone().saturating_sub(1));
+                tile.set_home_pheromone(tile.home_pheromone().saturating_sub(1));
+            }
+        }
+        let (nest_x, nest_y) = self.nest;
+        if let Some(tile) = self.get_tile_mut(nest_x, nest_y) {
+            tile.set_home_pheromone(7);
+        }
+    }
+
+    // Spiders wander and every predator strikes at one adjacent ant per tick
+    fn update_predators(&mut self, rng: &mut impl rand::Rng) {
+        let mut dead_ants: Vec<AntId> = Vec::new();
+
+        for p in 0..self.predators.len() {
+            if !self.predators[p].is_alive() {
+                continue;
+            }
+            if self.predators[p].roams() && rng.gen_range(0..100) < constants::SPIDER_MOVE_CHANCE_PERCENTAGE {
+                let (x, y) = (self.predators[p].x, self.predators[p].y);
+                let (dx, dy) = ant::Direction::from_bits(rng.gen_range(0..ant::Direction::COUNT)).delta();
+                if let Some((nx, ny)) = self.neighbour(x, y, dx, dy)
+                    && self.is_empty(nx, ny)
+                    && self.ant_count(nx, ny) == 0
+                    && !self.is_nest(nx, ny)
+                {
+                    self.add_object(x, y, Objects::None);
+                    self.add_object(nx, ny, Objects::Predator);
+                    self.predators[p].x = nx;
+                    self.predators[p].y = ny;
+                }
+            }
+
+            let (x, y) = (self.predators[p].x, self.predators[p].y);
+            let nearby: Vec<AntId> = self.ants_around(x, y).into_iter().filter(|id| !dead_ants.contains(id)).collect();
+            if !nearby.is_empty() && rng.gen_range(0..100) < constants::PREDATOR_STRIKE_CHANCE_PERCENTAGE {
+                let victim = nearby[rng.gen_range(0..nearby.len())];
+                let Some(slot) = self.ants.slot(victim) else {
+                    continue;
+                };
+                if self.ants.damage(slot, 1) {
+                    dead_ants.push(victim);
+                }
+            }
+        }
+
+        // Clear out the slain predators
+        let mut p = 0;
+        while p < self.predators.len() {
+            if self.predators[p].is_alive() {
+                p += 1;
+                continue;
+            }
+            let predator = self.predators.swap_remove(p);
+            self.add_object(predator.x, predator.y, Objects::None);
+            self.stats.predators_killed += 1;
+        }
+
+        for id in dead_ants {
+            self.kill_ant(id);
+        }
+    }
+
+    /// Removes the ant, dropping any food it carried where it fell
+    pub fn kill_ant(&mut self, id: AntId) {
+        let Some((ant, x, y)) = self.remove_ant(id) else {
+            return;
+        };
+        if matches!(ant.carrying(), ant::Carrying::Food) && self.is_empty(x, y) {
+            self.add_object(x, y, Objects::Food);
+        }
+        self.stats.ants_killed += 1;
+        self.emit(EventKind::AntDied, (x, y), Some(id));
+    }
+
+    // Rain washes pheromones off the surface and floods open tunnel entrances;
+    // the water drains away once it stops.
+    fn update_weather(&mut self, rng: &mut impl rand::Rng) {
+        if self.environment.is_raining() {
+            let surface_end = self.idx(0, self.ground_top);
+            for tile in self.grid[..surface_end].iter_mut() {
+                tile.set_pheromone(0);
+                tile.set_home_pheromone(0);
+            }
+            // Water pools in the mouths of tunnels that lead further down, but
+            // never on top of an ant
+            let y = self.ground_top;
+            for x in 0..self.width {
+                let leads_down = self.get_tile(x, y + 1).is_some_and(|tile| !matches!(tile.object(), Objects::Obstacle | Objects::Soil));
+                if self.is_empty(x, y) && leads_down && self.ant_count(x, y) == 0 {
+                    self.add_object(x, y, Objects::Water);
+                }
+            }
+        } else {
+            for tile in self.grid.iter_mut() {
+                if matches!(tile.object(), Objects::Water) && rng.gen_range(0..100) < constants::WATER_DRAIN_CHANCE_PERCENTAGE {
+                    tile.set_object(Objects::None);
+                }
+            }
+        }
+    }
+
+    /// Turns solid ground at (x, y) into open tunnel
+    pub fn dig(&mut self, x: usize, y: usize) -> bool {
+        match self.get_tile_mut(x, y) {
+            Some(tile) if matches!(tile.object(), Objects::Obstacle) => {
+                tile.set_object(Objects::None);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn is_empty(&self, x: usize, y: usize) -> bool {
+        matches!(self.get_tile(x, y).map(Tile::object), Some(Objects::None))
+    }
+
+    // Where an ant on (x, y) facing `ahead` can dump soil: the cell ahead, or else
+    // any other neighbour, as long as it is open, away from the nest and has no
+    // ants on it to bury
+    fn soil_spot(&self, x: usize, y: usize, ahead: (isize, isize)) -> Option<(usize, usize)> {
+        let around = (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))).filter(|&offset| offset != (0, 0) && offset != ahead);
+        std::iter::once(ahead)
+            .chain(around)
+            .filter_map(|(dx, dy)| self.neighbour(x, y, dx, dy))
+            .find(|&(nx, ny)| self.is_empty(nx, ny) && self.ant_count(nx, ny) == 0 && !self.is_nest(nx, ny))
+    }
+
+    // Falling-sand pass: loose soil drops straight down, otherwise slides diagonally.
+    // Rows are scanned bottom-up so a grain moves at most one cell per tick.
+    fn settle_soil(&mut self, rng: &mut impl rand::Rng) {
+        let left_to_right = rng.gen_range(0..2) == 0;
+
+        for y in (0..self.height - 1).rev() {
+            for i in 0..self.width {
+                let x = if left_to_right { i } else { self.width - 1 - i };
+                let index = self.idx(x, y);
+                if !matches!(self.grid[index].object(), Objects::Soil) {
+                    continue;
+                }
+
+                let (first, second) = if rng.gen_range(0..2) == 0 { (-1, 1) } else { (1, -1) };
+                let target = [0, first, second]
+                    .into_iter()
+                    .filter_map(|dx| self.neighbour(x, y, dx, 1))
+                    .find(|&(tx, ty)| self.is_empty(tx, ty) && self.ant_count(tx, ty) == 0);
+
+                if let Some((tx, ty)) = target {
+                    let target = self.idx(tx, ty);
+                    self.grid[index].set_object(Objects::None);
+                    self.grid[target].set_object(Objects::Soil);
+                }
+            }
+        }
+    }
+
+    // Carries out what the ant's brain decided, within the rules of the world
+    fn apply_action(&mut self, slot: usize, action: Action, rng: &mut impl rand::Rng) {
+        let (x, y) = self.ants.position(slot);
+        match action {
+            Action::Rest => {
+                if let Some(state) = self.ants.state_mut(slot) {
+                    state.energy = (state.energy + constants::ANT_REST_ENERGY_GAIN).min(constants::ANT_MAX_ENERGY);
+                }
+            }
+            Action::Face(direction) => self.ants.ant_mut(slot).set_direction(direction),
+            Action::Move(direction) => {
+                self.ants.ant_mut(slot).set_direction(direction);
+                let (dx, dy) = direction.delta();
+                if let Some((nx, ny)) = self.neighbour(x, y, dx, dy)
+                    && self.move_slot(slot, nx, ny)
+                {
+                    self.lay_pheromone(slot, (x, y));
+                    self.integrate_path(slot, direction, (nx, ny), rng);
+                }
+            }
+            // Nobody forages in the rain
+            Action::PickUp => {
+                if matches!(self.ants.ant(slot).carrying(), ant::Carrying::None)
+                    && !self.environment.is_raining()
+                    && let Some(tile) = self.get_tile_mut(x, y)
+                    && matches!(tile.object(), Objects::Food)
+                {
+                    tile.set_object(Objects::None);
+                    tile.set_pheromone(3);
+                    let ant = self.ants.ant_mut(slot);
+                    ant.set_carrying(ant::Carrying::Food);
+                    ant.set_direction(ant.direction().reverse());
+                    self.emit(EventKind::FoodPickedUp, (x, y), Some(self.ants.id(slot)));
+                }
+            }
+            Action::Drop => match self.ants.ant(slot).carrying() {
+                ant::Carrying::Food if self.is_nest(x, y) => {
+                    self.stats.food_stored += 1;
+                    let ant = self.ants.ant_mut(slot);
+                    ant.set_carrying(ant::Carrying::None);
+                    ant.set_direction(ant.direction().reverse());
+                    self.emit(EventKind::FoodDelivered, (x, y), Some(self.ants.id(slot)));
+                }
+                ant::Carrying::Food if self.is_empty(x, y) => {
+                    self.add_object(x, y, Objects::Food);
+                    self.ants.ant_mut(slot).set_carrying(ant::Carrying::None);
+                }
+                ant::Carrying::Soil => {
+                    if let Some((sx, sy)) = self.soil_spot(x, y, self.ants.ant(slot).direction().delta()) {
+                        self.add_object(sx, sy, Objects::Soil);
+                        self.ants.ant_mut(slot).set_carrying(ant::Carrying::None);
+                    }
+                }
+                _ => {}
+            },
+            Action::Dig => {
+                let (dx, dy) = self.ants.ant(slot).direction().delta();
+                if matches!(self.ants.ant(slot).carrying(), ant::Carrying::None)
+                    && let Some((nx, ny)) = self.neighbour(x, y, dx, dy)
+                    && self.dig(nx, ny)
+                {
+                    self.ants.ant_mut(slot).set_carrying(ant::Carrying::Soil);
+                    self.emit(EventKind::TileDug, (nx, ny), Some(self.ants.id(slot)));
+                }
+            }
+            // Hit the weakest adjacent predator with the ant's strength
+            Action::Attack => {
+                let strength = self.ants.strength(slot);
+                let topology = self.topology;
+                let size = (self.width, self.height);
+                if let Some(predator) = self
+                    .predators
+                    .iter_mut()
+                    .filter(|predator| {
+                        let (dx, dy) = topology.displacement((x, y), (predator.x, predator.y), size);
+                        predator.is_alive() && dx.abs() <= 1 && dy.abs() <= 1
+                    })
+                    .min_by_key(|predator| predator.health)
+                {
+                    predator.health = predator.health.saturating_sub(strength);
+                }
+            }
+        }
+    }
+
+    // Walking tires an ant out and the nest feeds it back up. Every step is also
+    // added to the ant's remembered way home, now and then a turn off; reaching
+    // the nest clears any error that built up.
+    fn integrate_path(&mut self, slot: usize, direction: ant::Direction, to: (usize, usize), rng: &mut impl rand::Rng) {
+        let at_nest = self.is_nest(to.0, to.1);
+        let remembered = if rng.gen_range(0..100) < self.path_noise {
+            if rng.gen_range(0..2) == 0 { direction.turn_left() } else { direction.turn_right() }
+        } else {
+            direction
+        };
+        let Some(state) = self.ants.state_mut(slot) else {
+            return;
+        };
+        if at_nest {
+            state.energy = constants::ANT_MAX_ENERGY;
+            state.home = (0, 0);
+        } else {
+            let (dx, dy) = remembered.delta();
+            let (hx, hy) = self.topology.shorten((state.home.0 as isize - dx, state.home.1 as isize - dy), (self.width, self.height));
+            state.energy = state.energy.saturating_sub(1);
+            state.home = (hx as i32, hy as i32);
+        }
+    }
+
+    // Food carriers mark their trail at full strength; outbound ants extend the
+    // home gradient one step weaker than where they came from.
+    fn lay_pheromone(&mut self, slot: usize, from: (usize, usize)) {
+        let (x, y) = self.ants.position(slot);
+        let previous_home = self.grid[self.idx(from.0, from.1)].home_pheromone();
+        let carrying_food = matches!(self.ants.ant(slot).carrying(), ant::Carrying::Food);
+        let index = self.idx(x, y);
+        let tile = &mut self.grid[index];
+
+        if carrying_food {
+            tile.set_pheromone(3);
+        } else {
+            tile.set_home_pheromone(tile.home_pheromone().max(previous_home.saturating_sub(1)));
+        }
+    }
+
+    /// Direction of the strongest neighbouring home pheromone, if any beats the current tile
+    pub fn home_direction(&self, x: usize, y: usize) -> Option<ant::Direction> {
+        let mut best_level = self.get_tile(x, y).map_or(0, Tile::home_pheromone);
+        let mut best = None;
+        for dy in -1..=1isize {
+            for dx in -1..=1isize {
+                if let Some((nx, ny)) = self.neighbour(x, y, dx, dy)
+                    && let Some(tile) = self.get_tile(nx, ny)
+                    && tile.home_pheromone() > best_level
+                {
+                    best_level = tile.home_pheromone();
+                    best = ant::Direction::from_delta(dx, dy);
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::sync::{Arc, Mutex};
+
+    fn world_from(rows: &[&str]) -> World {
+        let mut rng = StdRng::seed_from_u64(1);
+        World::from_map(&mut rng, &Params::default(), &WorldSize::default(), rows).unwrap()
+    }
+
+    #[test]
+    fn threads_do_not_change_the_outcome() {
+        let run = |threads| {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut world = World::generate(&mut rng, &Params::default(), &WorldSize { ants: 200, ..WorldSize::default() });
+            world.threads = threads;
+            for _ in 0..300 {
+                world.step(&mut rng);
+            }
+            let mut ants: Vec<_> = world.ants().iter().map(|(slot, ant, position)| (world.ants().id(slot), ant.clone(), position)).collect();
+            ants.sort_by_key(|&(id, ..)| id);
+            (world.grid().to_vec(), ants)
+        };
+        let (single, threaded) = (run(1), run(4));
+        assert!(!single.1.is_empty());
+        assert!(single.0 == threaded.0, "grids differ");
+        assert!(single.1 == threaded.1, "ants differ");
+    }
+
+    #[test]
+    fn soil_never_lands_on_ants() {
+        let mut world = world_from(&[
+            "..:..",
+            "..X..",
+            "#####",
+        ]);
+        let mut rng = StdRng::seed_from_u64(1);
+        world.settle_soil(&mut rng);
+        assert!(!world.get_tile(2, 1).unwrap().is(Objects::Soil));
+        assert_eq!(world.grid().iter().filter(|tile| tile.is(Objects::Soil)).count(), 1);
+
+        // Facing another ant, the load goes to the first free neighbour instead
+        let world = world_from(&[
+            ".......",
+            ".XX....",
+            "#######",
+        ]);
+        assert_eq!(world.soil_spot(1, 1, (1, 0)), Some((0, 0)));
+        assert_eq!(world.soil_spot(1, 1, (-1, 0)), Some((0, 1)));
+    }
+
+    #[test]
+    fn rain_floods_only_empty_tunnel_mouths() {
+        let mut world = world_from(&[
+            "..........",
+            "#.#.#X#.##",
+            "#.###.##.#",
+            "##########",
+        ]);
+        let mut rng = StdRng::seed_from_u64(1);
+        while !world.environment.is_raining() {
+            world.environment.advance(&mut rng);
+        }
+        world.update_weather(&mut rng);
+        let flooded: Vec<usize> = (0..world.width).filter(|&x| world.get_tile(x, 1).unwrap().is(Objects::Water)).collect();
+        assert_eq!(flooded, vec![1]);
+    }
+
+    #[test]
+    fn home_vector_reaches_across_wide_worlds() {
+        let width = 40_000;
+        let (sky, ground) = (format!("N{}X", ".".repeat(width - 2)), "#".repeat(width));
+        let mut world = world_from(&[&sky, &ground]);
+        let slot = world.ants().len() - 1;
+        assert_eq!(world.ants().state(slot).unwrap().home, (1 - width as i32, 0));
+
+        // Stepping further away still counts up past i16::MAX
+        world.integrate_path(slot, ant::Direction::Right, (width - 1, 0), &mut StdRng::seed_from_u64(1));
+        assert_eq!(world.ants().state(slot).unwrap().home, (-(width as i32), 0));
+    }
+
+    #[test]
+    fn wide_soldiers_hit_harder_than_packed_ones() {
+        let strikes_to_kill = |compact| {
+            let size = WorldSize { compact, ..WorldSize::default() };
+            let mut world = World::from_map(&mut StdRng::seed_from_u64(1), &Params::default(), &size, &["X.@.........", "############"]).unwrap();
+            let id = world.add_ant(3, 0, ant::Ant::with_role(ant::AntRole::Soldier)).unwrap();
+            let slot = world.ants().slot(id).unwrap();
+            let mut strikes = 0;
+            while world.predators[0].is_alive() {
+                world.apply_action(slot, Action::Attack, &mut StdRng::seed_from_u64(1));
+                strikes += 1;
+            }
+            strikes
+        };
+        // Strength 4 only fits in the wide record; packed soldiers top out at 3
+        assert_eq!(strikes_to_kill(false), 2);
+        assert_eq!(strikes_to_kill(true), 3);
+    }
+
+    #[test]
+    fn predators_kill_ants_they_catch() {
+        let mut world = world_from(&["X...........", "############"]);
+        assert!(world.add_predator(PredatorKind::Antlion, 1, 0));
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..100 {
+            world.update_predators(&mut rng);
+        }
+        assert_eq!(world.ants().len(), 0);
+        assert_eq!(world.stats.ants_killed, 1);
+        assert_eq!(world.stats.predators_killed, 0);
+    }
+
+    #[test]
+    fn finds_ants_within_a_radius() {
+        let world = world_from(&[
+            "X....",
+            "..X..",
+            "....X",
+            "#####",
+        ]);
+        assert_eq!(world.ants_within(2, 1, 1).len(), 1);
+        assert_eq!(world.ants_within(2, 1, 3).len(), 3);
+        assert_eq!(world.ants_within(4, 0, 0).len(), 0);
+    }
+
+    #[test]
+    fn observers_hear_events_from_before_the_first_step() {
+        let mut world = world_from(&[
+            "..N..",
+            "#####",
+        ]);
+        let heard = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&heard);
+        world.subscribe(Box::new(move |event: &Event| log.lock().unwrap().push(event.kind.name())));
+        world.step(&mut StdRng::seed_from_u64(1));
+        assert_eq!(heard.lock().unwrap().first(), Some(&"ColonyFounded"));
+    }
+}